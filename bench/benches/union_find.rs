@@ -0,0 +1,194 @@
+//! Times the union-find as a case tree grows, on the largest levels in `levels.json`.
+//!
+//! Each level starts with its own nodes, and then has theorems applied to it one after another.
+//! Every application splits the case, which clones the union-find,
+//! adds the theorem's nodes, connects some of them up, and then redraws,
+//! which looks up the class of every node.
+//! The `HashMap`-based union-find that came before is timed alongside, for comparison,
+//! doing exactly the same operations.
+
+#[allow(dead_code)]
+#[path = "../../src/level/case/union_find.rs"]
+mod union_find;
+
+mod hash_map {
+    use std::{cell::RefCell, collections::HashMap, hash::Hash, rc::Rc};
+
+    #[derive(Debug, Clone)]
+    pub struct UnionFind<T> {
+        tree: RefCell<HashMap<T, Node<T>>>,
+        cycles: HashMap<T, T>,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Node<T> {
+        Root { rank: u8 },
+        Child { parent: T },
+    }
+
+    impl<T: Copy + Eq + Hash> UnionFind<T> {
+        pub fn new() -> Rc<Self> {
+            Rc::new(Self {
+                tree: RefCell::new(HashMap::new()),
+                cycles: HashMap::new(),
+            })
+        }
+
+        fn find(&self, node: T) -> (T, u8) {
+            let x = self.tree.borrow().get(&node).copied();
+            match x {
+                Some(Node::Root { rank }) => (node, rank),
+                Some(Node::Child { parent }) => {
+                    let (root, rank) = self.find(parent);
+                    self.tree
+                        .borrow_mut()
+                        .insert(node, Node::Child { parent: root });
+                    (root, rank)
+                }
+                None => {
+                    self.tree.borrow_mut().insert(node, Node::Root { rank: 0 });
+                    (node, 0)
+                }
+            }
+        }
+
+        pub fn canonical(&self, node: T) -> T {
+            self.find(node).0
+        }
+
+        pub fn merge(&mut self, n1: T, n2: T) {
+            let (r1, rank1) = self.find(n1);
+            let (r2, rank2) = self.find(n2);
+            if r1 == r2 {
+                return;
+            }
+            let s1 = *self.cycles.get(&r1).unwrap_or(&r1);
+            let s2 = self.cycles.insert(r2, s1).unwrap_or(r2);
+            self.cycles.insert(r1, s2);
+
+            let mut tree = self.tree.borrow_mut();
+            if rank1 < rank2 {
+                tree.insert(r1, Node::Child { parent: r2 });
+            } else {
+                tree.insert(r2, Node::Child { parent: r1 });
+                if rank1 == rank2 {
+                    tree.insert(r1, Node::Root { rank: rank1 + 1 });
+                }
+            }
+        }
+
+        pub fn iter_class(&self, e: T) -> impl Iterator<Item = T> + '_ {
+            let mut node = e;
+            std::iter::once(e).chain(std::iter::from_fn(move || {
+                node = *self.cycles.get(&node).unwrap_or(&node);
+                (node != e).then_some(node)
+            }))
+        }
+    }
+}
+
+impl union_find::Key for usize {
+    fn index(self) -> usize {
+        self
+    }
+
+    fn from_index(index: usize) -> Self {
+        index
+    }
+}
+
+const APPLICATIONS: usize = 40;
+const THEOREM_NODES: usize = 8;
+const REPEATS: usize = 200;
+
+/// A small deterministic generator, so every run does the same work.
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % n
+    }
+}
+
+/// Returns a checksum, so the work can't be optimized away.
+fn dense(level_nodes: usize, seed: u64) -> usize {
+    let mut rng = Lcg(seed);
+    let mut uf = union_find::UnionFind::<usize>::new();
+    let mut history = Vec::new();
+    let mut nodes = level_nodes;
+    let mut checksum = 0;
+    for _ in 0..APPLICATIONS {
+        history.push(uf.clone());
+        nodes += THEOREM_NODES;
+        for _ in 0..THEOREM_NODES / 2 {
+            uf.merge(rng.below(nodes), rng.below(nodes));
+        }
+        checksum += (0..nodes).map(|n| uf.canonical(n)).sum::<usize>();
+        checksum += uf.iter_class(rng.below(nodes)).count();
+    }
+    checksum + history.len()
+}
+
+fn hash_map(level_nodes: usize, seed: u64) -> usize {
+    let mut rng = Lcg(seed);
+    let mut uf = hash_map::UnionFind::<usize>::new();
+    let mut history = Vec::new();
+    let mut nodes = level_nodes;
+    let mut checksum = 0;
+    for _ in 0..APPLICATIONS {
+        // Splitting kept the old case, so `make_mut` had to copy.
+        history.push(uf.clone());
+        nodes += THEOREM_NODES;
+        for _ in 0..THEOREM_NODES / 2 {
+            std::rc::Rc::make_mut(&mut uf).merge(rng.below(nodes), rng.below(nodes));
+        }
+        checksum += (0..nodes).map(|n| uf.canonical(n)).sum::<usize>();
+        checksum += uf.iter_class(rng.below(nodes)).count();
+    }
+    checksum + history.len()
+}
+
+fn time(f: impl Fn(usize, u64) -> usize, level_nodes: usize) -> std::time::Duration {
+    let start = std::time::Instant::now();
+    let mut checksum = 0;
+    for seed in 0..REPEATS as u64 {
+        checksum += f(level_nodes, seed);
+    }
+    std::hint::black_box(checksum);
+    start.elapsed() / REPEATS as u32
+}
+
+fn main() {
+    let levels: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../levels.json"))
+            .expect("Failed to read levels.json."),
+    )
+    .expect("Failed to parse levels.json.");
+
+    let mut sizes: Vec<(usize, &str)> = levels["levels"]
+        .as_object()
+        .expect("levels.json has no levels.")
+        .iter()
+        .map(|(name, level)| (level["nodes"].as_array().map_or(0, Vec::len), name.as_str()))
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+    println!("{APPLICATIONS} theorem applications, each adding {THEOREM_NODES} nodes.");
+    println!(
+        "{:<16}{:>8}{:>16}{:>16}",
+        "level", "nodes", "dense", "hash map"
+    );
+    for &(nodes, name) in sizes.iter().take(5) {
+        println!(
+            "{:<16}{:>8}{:>16?}{:>16?}",
+            name,
+            nodes,
+            time(dense, nodes),
+            time(hash_map, nodes)
+        );
+    }
+}
@@ -0,0 +1,257 @@
+// A small subset of Markdown, enough for the book.
+//
+// Blocks are separated by blank lines, and are one of:
+// - Headings, starting with `#`.
+// - Lists, with items starting with `- `, nested by indentation.
+// - Raw HTML, starting with a block-level tag such as `<div>` or `<svg>`. This is passed through unchanged.
+// - Paragraphs, for anything else.
+//
+// Within headings, list items and paragraphs, `*emphasis*`, `**strong**`, `` `code` ``
+// and `[links](#Page)` are recognized. Inline HTML is passed through unchanged.
+// A link to `level:name` becomes a button that opens the page's sandbox level of that name.
+
+const BLOCK_TAGS: &[&str] = &[
+    "div",
+    "svg",
+    "p",
+    "ul",
+    "ol",
+    "table",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "pre",
+    "blockquote",
+    "hr",
+    "figure",
+];
+
+/// Convert markdown to HTML.
+/// The targets of all links are pushed onto `links`, so they can be checked.
+pub fn to_html(markdown: &str, links: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    let mut block = Vec::new();
+
+    for line in markdown.lines().chain([""]) {
+        if !line.trim().is_empty() {
+            block.push(line);
+            continue;
+        }
+        if block.is_empty() {
+            continue;
+        }
+
+        let first = block[0].trim_start();
+        if is_raw_html(first) {
+            for line in &block {
+                out += line;
+                out.push('\n');
+            }
+        } else if first.starts_with("- ") {
+            list(&block, links, &mut out);
+        } else if let Some((level, heading)) = heading(first) {
+            out += &format!("<h{level}>{}</h{level}>", inline(heading, links));
+            if block.len() > 1 {
+                out += &format!("<p>{}</p>", inline(&join(&block[1..]), links));
+            }
+        } else {
+            out += &format!("<p>{}</p>", inline(&join(&block), links));
+        }
+
+        block.clear();
+    }
+
+    out
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn is_raw_html(line: &str) -> bool {
+    let Some(tag) = line.strip_prefix('<') else {
+        return false;
+    };
+    let name = tag
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .next()
+        .unwrap_or_default();
+    BLOCK_TAGS.contains(&name.to_ascii_lowercase().as_str())
+}
+
+// The page title is the `<h1>`, so `#` gives an `<h2>`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = line[level..].strip_prefix(' ')?;
+    (1..=5).contains(&level).then_some((level + 1, rest.trim()))
+}
+
+fn join(lines: &[&str]) -> String {
+    lines
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn list(lines: &[&str], links: &mut Vec<String>, out: &mut String) {
+    // Each item, with its indentation.
+    let mut items: Vec<(usize, String)> = Vec::new();
+    for line in lines {
+        let indent = line.len() - line.trim_start().len();
+        match line.trim_start().strip_prefix("- ") {
+            Some(item) => items.push((indent, item.trim().to_owned())),
+            None => {
+                // A continuation of the previous item.
+                if let Some((_, item)) = items.last_mut() {
+                    item.push(' ');
+                    *item += line.trim();
+                }
+            }
+        }
+    }
+    nested_list(&items, links, out);
+}
+
+fn nested_list(items: &[(usize, String)], links: &mut Vec<String>, out: &mut String) {
+    *out += "<ul>";
+    let mut i = 0;
+    while i < items.len() {
+        let (indent, item) = &items[i];
+        *out += "<li>";
+        *out += &inline(item, links);
+        let children = items[i + 1..]
+            .iter()
+            .take_while(|(child, _)| child > indent)
+            .count();
+        if children > 0 {
+            nested_list(&items[i + 1..i + 1 + children], links, out);
+        }
+        *out += "</li>";
+        i += 1 + children;
+    }
+    *out += "</ul>";
+}
+
+fn inline(text: &str, links: &mut Vec<String>) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some(i) = rest.find(['*', '`', '[']) {
+        out += &rest[..i];
+        rest = &rest[i..];
+
+        if let Some((inner, after)) = delimited(rest, "**", "**") {
+            out += &format!("<strong>{}</strong>", inline(inner, links));
+            rest = after;
+        } else if let Some((inner, after)) = delimited(rest, "*", "*") {
+            out += &format!("<em>{}</em>", inline(inner, links));
+            rest = after;
+        } else if let Some((inner, after)) = delimited(rest, "`", "`") {
+            out += &format!("<code>{}</code>", escape(inner));
+            rest = after;
+        } else if let Some((label, (href, after))) = delimited(rest, "[", "](")
+            .and_then(|(label, after)| Some((label, delimited(after, "", ")")?)))
+        {
+            links.push(href.to_owned());
+            out += &match href.strip_prefix("level:") {
+                Some(level) => format!(
+                    r#"<a class="button green" data-book-level="{}">{}</a>"#,
+                    escape(level),
+                    inline(label, links)
+                ),
+                None => format!(r#"<a href="{}">{}</a>"#, escape(href), inline(label, links)),
+            };
+            rest = after;
+        } else {
+            // Not markup after all.
+            let c = rest.chars().next().unwrap();
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    out += rest;
+    out
+}
+
+/// If `text` starts with `open`, and contains `close` later on,
+/// return what's between them, and what's after.
+fn delimited<'a>(text: &'a str, open: &str, close: &str) -> Option<(&'a str, &'a str)> {
+    let text = text.strip_prefix(open)?;
+    let end = text.find(close).filter(|&end| end > 0 || open.is_empty())?;
+    Some((&text[..end], &text[end + close.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(markdown: &str) -> String {
+        to_html(markdown, &mut Vec::new())
+    }
+
+    #[test]
+    fn paragraphs_are_separated_by_blank_lines() {
+        assert_eq!(
+            html("Hello\nworld\n\n\nNext"),
+            "<p>Hello world</p><p>Next</p>"
+        );
+    }
+
+    #[test]
+    fn headings_are_one_level_below_the_title() {
+        assert_eq!(html("# Title\nText"), "<h2>Title</h2><p>Text</p>");
+        assert_eq!(html("### Title"), "<h4>Title</h4>");
+        assert_eq!(html("###### Too deep"), "<p>###### Too deep</p>");
+        assert_eq!(html("#hashtag"), "<p>#hashtag</p>");
+    }
+
+    #[test]
+    fn lists_nest_by_indentation() {
+        assert_eq!(
+            html("- a\n  - b\n  more\n- c"),
+            "<ul><li>a<ul><li>b more</li></ul></li><li>c</li></ul>"
+        );
+    }
+
+    #[test]
+    fn raw_html_is_passed_through() {
+        let block = "<div class=\"x\">\n*not emphasis*\n</div>";
+        assert_eq!(html(block), format!("{block}\n"));
+        assert_eq!(html("<span>inline</span>"), "<p><span>inline</span></p>");
+    }
+
+    #[test]
+    fn inline_markup() {
+        assert_eq!(
+            html("**bold** and *em* and `a<b`"),
+            "<p><strong>bold</strong> and <em>em</em> and <code>a&lt;b</code></p>"
+        );
+        assert_eq!(html("2 * 3, or [1]"), "<p>2 * 3, or [1]</p>");
+    }
+
+    #[test]
+    fn links_are_collected() {
+        let mut links = Vec::new();
+        assert_eq!(
+            to_html("[see](#Page) and [play](level:∧-intro)", &mut links),
+            r##"<p><a href="#Page">see</a> and <a class="button green" data-book-level="∧-intro">play</a></p>"##
+        );
+        assert_eq!(links, ["#Page", "level:∧-intro"]);
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            escape(r#"<a href="x">&</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
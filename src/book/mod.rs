@@ -0,0 +1,225 @@
+// The content of the book is part of the level pack.
+// When the pack loads, its pages are rendered into the `#book` element of `index.html`,
+// and shown with the CSS `:target` selector, so navigating is just a matter of changing the URL hash.
+// Pages can also embed small levels to try out, which are played in the main view, on top of the book.
+
+mod markdown;
+
+use anyhow::*;
+use std::collections::HashMap;
+
+/// A page of the book, as an index into `Book::pages`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookPage(usize);
+
+#[derive(Default)]
+pub struct Book {
+    pages: Vec<Page>,
+    indices: HashMap<String, BookPage>,
+}
+
+struct Page {
+    id: String,
+    title: String,
+    html: String,
+    sandboxes: HashMap<String, Sandbox>,
+    /// The title and HTML in other languages, by language code.
+    translations: HashMap<String, (String, String)>,
+}
+
+/// A page of the book, as written in the level pack.
+pub struct PageSource {
+    pub id: String,
+    pub title: String,
+    /// The page's content, in markdown.
+    pub content: String,
+    pub sandboxes: HashMap<String, Sandbox>,
+    /// The title and content in other languages, by language code.
+    pub translations: HashMap<String, (String, String)>,
+}
+
+/// A level embedded in a book page.
+/// It isn't part of the map, so nothing done in it is saved.
+pub struct Sandbox {
+    pub spec: crate::level::LevelSpec,
+    pub panzoom: crate::render::PanZoom,
+    pub text_box: Option<crate::locale::Translated>,
+    pub unlocks: crate::game_data::Unlocks,
+}
+
+impl Sandbox {
+    pub fn load(&self) -> crate::level::State {
+        crate::level::State::new(
+            &self.spec,
+            self.panzoom,
+            self.text_box.clone().map(|text| (text, None)),
+            None,
+            Vec::new(),
+            self.unlocks,
+            false,
+        )
+    }
+}
+
+impl Book {
+    /// Build a book from its pages, in reading order.
+    /// Fails if ids are repeated, or use anything but ASCII letters, digits, `_` and `-`,
+    /// or if a page links to a page or sandbox that doesn't exist.
+    pub fn new(pages: impl IntoIterator<Item = PageSource>) -> Result<Self> {
+        let mut book = Self::default();
+        let mut links = Vec::new();
+
+        for PageSource {
+            id,
+            title,
+            content,
+            sandboxes,
+            translations,
+        } in pages
+        {
+            // Ids go into the page's HTML and URL unescaped, so they're kept to characters safe in both.
+            if id.is_empty()
+                || !id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                bail!("Invalid book page id: {:?}.", id);
+            }
+            if book.indices.contains_key(&id) {
+                bail!("Duplicate book page: {}.", id);
+            }
+
+            let mut page_links = Vec::new();
+            let html = markdown::to_html(&content, &mut page_links);
+            let translations = translations
+                .into_iter()
+                .map(|(language, (title, content))| {
+                    (
+                        language,
+                        (title, markdown::to_html(&content, &mut page_links)),
+                    )
+                })
+                .collect();
+            for link in page_links {
+                if let Some(level) = link.strip_prefix("level:") {
+                    if !sandboxes.contains_key(level) {
+                        bail!("Book page {} links to unknown level {}.", id, level);
+                    }
+                } else if let Some(page) = link.strip_prefix('#') {
+                    if !page.is_empty() && !crate::route::is_route(&link) {
+                        links.push((id.clone(), page.to_owned()));
+                    }
+                }
+            }
+
+            book.indices.insert(id.clone(), BookPage(book.pages.len()));
+            book.pages.push(Page {
+                id,
+                title,
+                html,
+                sandboxes,
+                translations,
+            });
+        }
+
+        for (from, to) in links {
+            if !book.indices.contains_key(&to) {
+                bail!("Book page {} links to unknown page {}.", from, to);
+            }
+        }
+
+        Ok(book)
+    }
+
+    pub fn page(&self, id: &str) -> Option<BookPage> {
+        self.indices.get(id).copied()
+    }
+
+    pub fn id(&self, page: BookPage) -> &str {
+        &self.pages[page.0].id
+    }
+
+    pub fn sandbox(&self, page: &str, level: &str) -> Option<&Sandbox> {
+        self.pages[self.page(page)?.0].sandboxes.get(level)
+    }
+
+    /// The first page, which serves as the table of contents.
+    pub fn first(&self) -> Option<BookPage> {
+        (!self.pages.is_empty()).then_some(BookPage(0))
+    }
+
+    pub fn next(&self, page: BookPage) -> Option<BookPage> {
+        (page.0 + 1 < self.pages.len()).then_some(BookPage(page.0 + 1))
+    }
+
+    pub fn prev(&self, page: BookPage) -> Option<BookPage> {
+        page.0.checked_sub(1).map(BookPage)
+    }
+
+    /// Replace the contents of the `#book` element with this book's pages, in the chosen language.
+    pub fn install(&self, strings: crate::locale::Strings) {
+        let mut html = String::new();
+        for (i, page) in self.pages.iter().enumerate() {
+            let (title, content) = strings
+                .language()
+                .and_then(|language| page.translations.get(language))
+                .map_or((&page.title, &page.html), |(title, html)| (title, html));
+
+            let page_link = |page: Option<BookPage>, arrow: &str| match page {
+                Some(page) => format!(
+                    r##"<a href="#{}" class="button">{arrow}</a>"##,
+                    self.id(page)
+                ),
+                None => format!(r#"<a class="button disabled">{arrow}</a>"#),
+            };
+
+            html += &format!(
+                r##"<div id="{}" class="book background">{}<div class="book-content"><a href="#" class="book-close">❌</a><h1>{}</h1>{}</div>{}</div>"##,
+                page.id,
+                page_link(self.prev(BookPage(i)), "◄"),
+                markdown::escape(title),
+                content,
+                page_link(self.next(BookPage(i)), "►"),
+            );
+        }
+
+        web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .get_element_by_id("book")
+            .unwrap()
+            .set_inner_html(&html);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(id: &str, content: &str) -> PageSource {
+        PageSource {
+            id: id.to_owned(),
+            title: "Title".to_owned(),
+            content: content.to_owned(),
+            sandboxes: HashMap::new(),
+            translations: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn page_ids_are_checked() {
+        assert!(Book::new([page("Thm_App-2", "")]).is_ok());
+        for id in ["", "/map", "a b", r#"x"><script>"#, "ünïcode"] {
+            assert!(Book::new([page(id, "")]).is_err(), "{id}");
+        }
+        assert!(Book::new([page("A", ""), page("A", "")]).is_err());
+    }
+
+    #[test]
+    fn links_are_checked() {
+        assert!(Book::new([page("A", "[next](#B)"), page("B", "[map](#/map)")]).is_ok());
+        assert!(Book::new([page("A", "[next](#C)"), page("B", "")]).is_err());
+        assert!(Book::new([page("A", "[try](level:x)")]).is_err());
+    }
+}
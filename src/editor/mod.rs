@@ -0,0 +1,385 @@
+//! An editor for the statements of levels, so that they needn't be written into `levels.json` by hand.
+//!
+//! The statement is checked after every change. Once it's valid, it can be play-tested,
+//! or exported as an entry for `levels.json`.
+
+use std::{collections::HashMap, rc::Rc};
+
+use anyhow::{anyhow, bail};
+use smallvec::SmallVec;
+
+use crate::{
+    level::{
+        self,
+        expression::{Definitions, Expression, Rational, Rewrites, Type, Var},
+        LevelSpec, Propositional,
+    },
+    render::PanZoom,
+};
+
+mod render;
+
+pub struct State {
+    nodes: Vec<Node>,
+    hypotheses: Vec<usize>,
+    conclusion: Option<usize>,
+    panzoom: PanZoom,
+    tool: Tool,
+    drag: Option<Drag>,
+    /// The name typed in for the next variable or numeral.
+    name: String,
+    definitions: Rc<Definitions>,
+    /// The statement, or why it isn't valid.
+    spec: anyhow::Result<LevelSpec>,
+    /// Whether the statement, if valid, is a propositional tautology.
+    propositional: Propositional,
+    /// The level being play-tested, if any.
+    pub playtest: Option<Box<level::State>>,
+}
+
+struct Node {
+    symbol: Symbol,
+    /// Unlike in a `LevelSpec`, inputs can be any node, until the statement is checked.
+    inputs: Vec<usize>,
+    position: [f64; 2],
+}
+
+#[derive(Debug, Clone)]
+pub enum Symbol {
+    And,
+    Or,
+    Implies,
+    Equal,
+    Variable(Var),
+    /// A function symbol of the pack, or a numeral.
+    Function(String, Type),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Move,
+    Connect,
+    Hypothesis,
+    Conclusion,
+    Delete,
+}
+
+struct Drag {
+    coord: [f64; 2],
+    /// `None` when panning.
+    node: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum Msg {
+    MouseDown(f64, f64, Option<usize>),
+    MouseMove(f64, f64),
+    MouseUp(f64, f64, Option<usize>),
+    MouseWheel(f64, f64, f64),
+    SetTool(Tool),
+    SetName(String),
+    AddVariable(Type),
+    AddSymbol(Symbol),
+    Playtest,
+}
+
+impl Symbol {
+    pub fn text(&self) -> &str {
+        match self {
+            Symbol::And => "∧",
+            Symbol::Or => "∨",
+            Symbol::Implies => "⇒",
+            Symbol::Equal => "=",
+            Symbol::Variable(Var(x, _)) => x,
+            Symbol::Function(f, _) => f,
+        }
+    }
+
+    fn ty(&self) -> Type {
+        match self {
+            Symbol::And | Symbol::Or | Symbol::Implies | Symbol::Equal => Type::TruthValue,
+            Symbol::Variable(Var(_, ty)) | Symbol::Function(_, ty) => *ty,
+        }
+    }
+
+    fn expression(&self, inputs: &[usize]) -> anyhow::Result<Expression<usize>> {
+        let inputs = SmallVec::from(inputs);
+        let pair = |inputs: SmallVec<[usize; 2]>| {
+            inputs.into_inner().map_err(|inputs| {
+                anyhow!(
+                    "`{}` needs 2 inputs, but has {}.",
+                    self.text(),
+                    inputs.len()
+                )
+            })
+        };
+        Ok(match self {
+            Symbol::And => Expression::And(inputs),
+            Symbol::Or => Expression::Or(inputs),
+            Symbol::Implies => Expression::Implies(pair(inputs)?),
+            Symbol::Equal => Expression::Equal(pair(inputs)?),
+            Symbol::Variable(v) if inputs.is_empty() => Expression::Variable(v.clone()),
+            Symbol::Variable(Var(v, _)) => bail!("Variable {} can't have inputs.", v),
+            Symbol::Function(f, ty) => Expression::Function(f.clone(), *ty, inputs),
+        })
+    }
+}
+
+impl State {
+    pub fn new(definitions: Rc<Definitions>) -> Self {
+        let mut state = Self {
+            nodes: Vec::new(),
+            hypotheses: Vec::new(),
+            conclusion: None,
+            panzoom: PanZoom::center([0., 0.], 10.),
+            tool: Tool::Move,
+            drag: None,
+            name: String::new(),
+            definitions,
+            spec: Err(anyhow!("")),
+            propositional: Propositional::Tautology,
+            playtest: None,
+        };
+        state.check();
+        state
+    }
+
+    pub fn update(&mut self, msg: Msg, rerender: &mut bool) {
+        match msg {
+            Msg::MouseDown(x, y, node) => {
+                match (self.tool, node) {
+                    (Tool::Move | Tool::Connect, _) | (_, None) => {
+                        self.drag = Some(Drag {
+                            coord: [x, y],
+                            node,
+                        })
+                    }
+                    (Tool::Hypothesis, Some(node)) => {
+                        if let Some(k) = self.hypotheses.iter().position(|&h| h == node) {
+                            self.hypotheses.remove(k);
+                        } else {
+                            self.hypotheses.push(node);
+                        }
+                        self.check();
+                    }
+                    (Tool::Conclusion, Some(node)) => {
+                        self.conclusion = Some(node);
+                        self.check();
+                    }
+                    (Tool::Delete, Some(node)) => {
+                        self.delete(node);
+                        self.check();
+                    }
+                }
+                *rerender = true;
+            }
+            Msg::MouseMove(x, y) => self.mouse_move(x, y, rerender),
+            Msg::MouseUp(x, y, target) => {
+                self.mouse_move(x, y, rerender);
+                let Some(Drag {
+                    node: Some(node), ..
+                }) = self.drag.take()
+                else {
+                    return;
+                };
+                match (self.tool, target) {
+                    (Tool::Connect, Some(target)) if target == node => {
+                        self.nodes[node].inputs.pop();
+                    }
+                    (Tool::Connect, Some(target)) => self.nodes[target].inputs.push(node),
+                    _ => {}
+                }
+                self.check();
+                *rerender = true;
+            }
+            Msg::MouseWheel(x, y, wheel) => {
+                self.panzoom.zoom(x, y, (wheel * 0.001).exp());
+                *rerender = true;
+            }
+            Msg::SetTool(tool) => {
+                self.tool = tool;
+                self.drag = None;
+                *rerender = true;
+            }
+            Msg::SetName(name) => self.name = name,
+            Msg::AddVariable(ty) => {
+                let name = self.name.trim();
+                if name.is_empty() {
+                    return;
+                }
+                let symbol = if ty == Type::RealNumber && Rational::parse(name).is_some() {
+                    Symbol::Function(name.to_owned(), ty)
+                } else {
+                    Symbol::Variable(Var(name.to_owned(), ty))
+                };
+                self.name.clear();
+                self.add(symbol);
+                *rerender = true;
+            }
+            Msg::AddSymbol(symbol) => {
+                self.add(symbol);
+                *rerender = true;
+            }
+            Msg::Playtest => {
+                self.playtest = self.spec.as_ref().ok().map(|spec| {
+                    Box::new(level::State::playtest(
+                        spec,
+                        PanZoom::fit(spec.nodes().iter().map(|&(_, position)| position)),
+                    ))
+                });
+                *rerender = true;
+            }
+        }
+    }
+
+    fn mouse_move(&mut self, x: f64, y: f64, rerender: &mut bool) {
+        let Some(Drag { coord, node }) = &mut self.drag else {
+            return;
+        };
+        let dx = x - coord[0];
+        let dy = y - coord[1];
+        coord[0] = x;
+        coord[1] = y;
+
+        match (self.tool, *node) {
+            (_, None) => {
+                self.panzoom.pan(dx, dy);
+
+                // Update coord in response to changing coordinate system.
+                coord[0] -= dx;
+                coord[1] -= dy;
+            }
+            (Tool::Move, Some(node)) => self.nodes[node].position = [x, y],
+            _ => {}
+        }
+        *rerender = true;
+    }
+
+    /// Add a node in the middle of the view.
+    fn add(&mut self, symbol: Symbol) {
+        let ([x0, y0], [x1, y1]) = self.panzoom.svg_corners;
+        self.nodes.push(Node {
+            symbol,
+            inputs: Vec::new(),
+            position: [(x0 + x1) / 2., (y0 + y1) / 2.],
+        });
+        self.check();
+    }
+
+    fn delete(&mut self, node: usize) {
+        self.nodes.remove(node);
+        let renumber = |n: usize| match n.cmp(&node) {
+            std::cmp::Ordering::Less => Some(n),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(n - 1),
+        };
+        for n in &mut self.nodes {
+            n.inputs = n.inputs.iter().copied().filter_map(renumber).collect();
+        }
+        self.hypotheses = self
+            .hypotheses
+            .iter()
+            .copied()
+            .filter_map(renumber)
+            .collect();
+        self.conclusion = self.conclusion.and_then(renumber);
+    }
+
+    fn check(&mut self) {
+        self.spec = self.build();
+        self.propositional = self
+            .spec
+            .as_ref()
+            .map_or(Propositional::Tautology, LevelSpec::propositional);
+    }
+
+    /// Check the statement, numbering the nodes so that inputs come first.
+    fn build(&self) -> anyhow::Result<LevelSpec> {
+        let Some(conclusion) = self.conclusion else {
+            bail!("Choose a conclusion.");
+        };
+
+        let mut variables = HashMap::new();
+        for node in &self.nodes {
+            if let Symbol::Variable(Var(v, ty)) = &node.symbol {
+                if *variables.entry(v).or_insert(ty) != ty {
+                    bail!("Variable {} is used with two different types.", v);
+                }
+            }
+        }
+
+        // Depth-first, so that each node is numbered after its inputs.
+        fn visit(
+            nodes: &[Node],
+            node: usize,
+            visiting: &mut Vec<bool>,
+            index: &mut Vec<Option<usize>>,
+            order: &mut Vec<usize>,
+        ) -> anyhow::Result<()> {
+            if index[node].is_some() {
+                return Ok(());
+            }
+            if visiting[node] {
+                bail!("`{}` is one of its own inputs.", nodes[node].symbol.text());
+            }
+            visiting[node] = true;
+            for &input in &nodes[node].inputs {
+                visit(nodes, input, visiting, index, order)?;
+            }
+            index[node] = Some(order.len());
+            order.push(node);
+            Ok(())
+        }
+        let mut visiting = vec![false; self.nodes.len()];
+        let mut index = vec![None; self.nodes.len()];
+        let mut order = Vec::with_capacity(self.nodes.len());
+        for node in 0..self.nodes.len() {
+            visit(&self.nodes, node, &mut visiting, &mut index, &mut order)?;
+        }
+        let index = |node: usize| index[node].expect("Every node was visited.");
+
+        let nodes = order
+            .into_iter()
+            .map(|node| {
+                let Node {
+                    symbol,
+                    inputs,
+                    position,
+                } = &self.nodes[node];
+                let expression = symbol.expression(inputs)?;
+                if !expression.tycheck(|&input| self.nodes[input].symbol.ty()) {
+                    bail!("The inputs of `{}` have the wrong types.", symbol.text());
+                }
+                Ok((expression.map(index), *position))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        for &hypothesis in &self.hypotheses {
+            if self.nodes[hypothesis].symbol.ty() != Type::TruthValue {
+                bail!(
+                    "Hypothesis `{}` is not a truth value.",
+                    self.nodes[hypothesis].symbol.text()
+                );
+            }
+        }
+        if self.nodes[conclusion].symbol.ty() != Type::TruthValue {
+            bail!(
+                "Conclusion `{}` is not a truth value.",
+                self.nodes[conclusion].symbol.text()
+            );
+        }
+
+        LevelSpec::new(
+            nodes,
+            self.hypotheses.iter().copied().map(index).collect(),
+            index(conclusion),
+            self.definitions.clone(),
+            Rewrites::default(),
+        )
+    }
+
+    /// The statement as an entry for `levels.json`, if it's valid.
+    pub fn export(&self) -> Option<String> {
+        self.spec.as_ref().ok().map(crate::game_data::export_level)
+    }
+}
@@ -0,0 +1,337 @@
+use super::*;
+use crate::{
+    architecture::Architecture,
+    game_data::GameData,
+    locale::{Strings, Text},
+    render::*,
+    Model,
+};
+use dodrio::{builder::*, bumpalo};
+use wasm_bindgen::JsCast;
+
+impl State {
+    pub fn render<'a>(
+        &self,
+        cx: &mut dodrio::RenderContext<'a>,
+        strings: Strings,
+        game_data: &GameData,
+    ) -> [dodrio::Node<'a>; 2] {
+        let status = match (&self.spec, self.propositional.counterexample()) {
+            (Ok(_), None) => strings.get_in(cx.bump, Text::StatementValid),
+            (Ok(_), Some(counterexample)) => bumpalo::collections::String::from_str_in(
+                &strings.format(Text::NotTautology, &counterexample),
+                cx.bump,
+            )
+            .into_bump_str(),
+            (Err(err), _) => bumpalo::format!(in cx.bump, "{}", err).into_bump_str(),
+        };
+        let col0 = div(cx.bump)
+            .attributes([attr("class", "col wide")])
+            .children([
+                self.render_canvas(cx),
+                div(cx.bump)
+                    .attributes([attr("class", "background disabled text-box")])
+                    .children([text(status)])
+                    .finish(),
+            ])
+            .finish();
+
+        let mut col1 = div(cx.bump).attributes([attr("class", "col narrow")]);
+
+        // Tools
+        for (tool, label) in [
+            (Tool::Move, Text::ToolMove),
+            (Tool::Connect, Text::ToolConnect),
+            (Tool::Hypothesis, Text::ToolHypothesis),
+            (Tool::Conclusion, Text::ToolConclusion),
+            (Tool::Delete, Text::ToolDelete),
+        ] {
+            col1 = col1.child(
+                div(cx.bump)
+                    .attributes([attr(
+                        "class",
+                        if self.tool == tool {
+                            "button green"
+                        } else {
+                            "button"
+                        },
+                    )])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::Editor(Msg::SetTool(tool))
+                    })])
+                    .children([text(strings.get_in(cx.bump, label))])
+                    .finish(),
+            );
+        }
+        col1 = col1.child(
+            div(cx.bump)
+                .attributes([attr("class", "background disabled text-box editor-help")])
+                .children([text(strings.get_in(
+                    cx.bump,
+                    match self.tool {
+                        Tool::Move => Text::HelpMove,
+                        Tool::Connect => Text::HelpConnect,
+                        Tool::Hypothesis => Text::HelpHypothesis,
+                        Tool::Conclusion => Text::HelpConclusion,
+                        Tool::Delete => Text::HelpDelete,
+                    },
+                ))])
+                .finish(),
+        );
+
+        // Variables and numerals
+        col1 = col1.child(
+            input(cx.bump)
+                .attributes([
+                    attr("class", "text-box theorem-search"),
+                    attr("placeholder", strings.get_in(cx.bump, Text::VariableName)),
+                    attr(
+                        "value",
+                        bumpalo::collections::String::from_str_in(&self.name, cx.bump)
+                            .into_bump_str(),
+                    ),
+                ])
+                .listeners([Model::listener(cx.bump, "input", |e| {
+                    let name = e
+                        .target()
+                        .unwrap()
+                        .dyn_into::<web_sys::HtmlInputElement>()
+                        .unwrap()
+                        .value();
+                    crate::Msg::Editor(Msg::SetName(name))
+                })])
+                .finish(),
+        );
+        for (ty, label) in [
+            (Type::TruthValue, Text::AddTruthVariable),
+            (Type::RealNumber, Text::AddNumberVariable),
+        ] {
+            col1 = col1.child(
+                div(cx.bump)
+                    .attributes([attr("class", "button blue")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::Editor(Msg::AddVariable(ty))
+                    })])
+                    .children([text(strings.get_in(cx.bump, label))])
+                    .finish(),
+            );
+        }
+
+        // Connectives and the pack's functions
+        let mut palette = div(cx.bump).attributes([attr("class", "palette")]);
+        for symbol in [Symbol::And, Symbol::Or, Symbol::Implies, Symbol::Equal]
+            .into_iter()
+            .chain(
+                game_data
+                    .functions()
+                    .iter()
+                    .map(|(f, &ty)| Symbol::Function(f.clone(), ty)),
+            )
+        {
+            let label =
+                bumpalo::collections::String::from_str_in(symbol.text(), cx.bump).into_bump_str();
+            palette = palette.child(
+                div(cx.bump)
+                    .attributes([attr("class", "button blue")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::Editor(Msg::AddSymbol(symbol.clone()))
+                    })])
+                    .children([text(label)])
+                    .finish(),
+            );
+        }
+        col1 = col1.child(palette.finish());
+
+        col1 = col1.child(
+            div(cx.bump)
+                .attributes([attr("style", "flex: 1;")])
+                .finish(),
+        );
+
+        // Play-test and export
+        let valid = self.spec.is_ok();
+        let mut playtest = div(cx.bump).attributes([attr(
+            "class",
+            if valid {
+                "button green"
+            } else {
+                "button disabled"
+            },
+        )]);
+        let mut export = div(cx.bump).attributes([attr(
+            "class",
+            if valid {
+                "button cyan"
+            } else {
+                "button disabled"
+            },
+        )]);
+        if valid {
+            playtest = playtest.listeners(
+                bumpalo::vec![in cx.bump; Model::listener(cx.bump, "click", |_| {
+                    crate::Msg::Editor(Msg::Playtest)
+                })],
+            );
+            export = export.listeners(bumpalo::vec![in cx.bump; crate::file::save_listener(
+                cx.bump,
+                |model| match &model.game_state {
+                    crate::GameState::Editor { editor_state } => {
+                        editor_state.export().unwrap_or_default()
+                    }
+                    _ => String::new(),
+                },
+                "level.json",
+            )]);
+        }
+        col1 = col1
+            .child(
+                playtest
+                    .children([text(strings.get_in(cx.bump, Text::Playtest))])
+                    .finish(),
+            )
+            .child(
+                export
+                    .children([text(strings.get_in(cx.bump, Text::ExportLevel))])
+                    .finish(),
+            )
+            .child(
+                div(cx.bump)
+                    .attributes([attr("class", "button blue")])
+                    .listeners([Model::listener(cx.bump, "click", |_| crate::Msg::GotoMap {
+                        recenter: false,
+                    })])
+                    .children([text(strings.get_in(cx.bump, Text::ReturnToMap))])
+                    .finish(),
+            );
+
+        [col0, col1.finish()]
+    }
+
+    fn render_canvas<'a>(&self, cx: &mut dodrio::RenderContext<'a>) -> dodrio::Node<'a> {
+        let mut builder = svg(cx.bump)
+            .attributes([
+                attr("id", "editor"),
+                attr("class", "background"),
+                attr("preserveAspectRatio", "xMidYMid meet"),
+                attr("font-size", "0.75"),
+                self.panzoom.viewbox(cx.bump),
+            ])
+            .listeners([
+                Model::listener(cx.bump, "mousedown", |e| {
+                    let (x, y) = to_svg_coords(e.dyn_into().unwrap());
+                    crate::Msg::Editor(Msg::MouseDown(x, y, None))
+                }),
+                Model::listener(cx.bump, "mousemove", |e| {
+                    let (x, y) = to_svg_coords(e.dyn_into().unwrap());
+                    crate::Msg::Editor(Msg::MouseMove(x, y))
+                }),
+                Model::listener(cx.bump, "mouseup", |e| {
+                    let (x, y) = to_svg_coords(e.dyn_into().unwrap());
+                    crate::Msg::Editor(Msg::MouseUp(x, y, None))
+                }),
+                Model::listener(cx.bump, "wheel", |e| {
+                    let e = e.dyn_into::<web_sys::WheelEvent>().unwrap();
+                    let wheel = e.delta_y();
+                    let (x, y) = to_svg_coords(e.into());
+                    crate::Msg::Editor(Msg::MouseWheel(x, y, wheel))
+                }),
+            ]);
+
+        // Wires, drawn from each node to the nodes using it, as in `LevelSpec::render`.
+        let mut outputs = vec![(Vec::new(), Vec::new()); self.nodes.len()];
+        for node in &self.nodes {
+            let x = (node.inputs.len() as f64 - 1.) / 2.;
+            for (ix, &input) in node.inputs.iter().enumerate() {
+                outputs[input].0.push(node.position);
+                outputs[input].1.push([-(ix as f64 - x), 1.]);
+            }
+        }
+        for (n, (node, (outputs, output_vectors))) in self.nodes.iter().zip(outputs).enumerate() {
+            let status = match node.symbol.ty() {
+                Type::TruthValue => {
+                    match (self.hypotheses.contains(&n), self.conclusion == Some(n)) {
+                        (true, true) => " known goal",
+                        (true, false) => " known",
+                        (false, true) => " goal",
+                        (false, false) => "",
+                    }
+                }
+                Type::RealNumber => " number",
+            };
+            for svg_node in level::render_wire(
+                cx,
+                &[node.position],
+                &outputs,
+                &output_vectors,
+                status,
+                None,
+                false,
+                None,
+            ) {
+                builder = builder.child(svg_node);
+            }
+        }
+
+        // The wire being connected
+        if let (
+            Tool::Connect,
+            Some(Drag {
+                coord: [x1, y1],
+                node: Some(node),
+            }),
+        ) = (self.tool, &self.drag)
+        {
+            let [x0, y0] = self.nodes[*node].position;
+            builder = builder.child(
+                path(cx.bump)
+                    .attributes([
+                        attr("class", "wire editor-connection"),
+                        attr(
+                            "d",
+                            bumpalo::format!(in cx.bump, "M {} {} L {} {}", x0, y0, x1, y1)
+                                .into_bump_str(),
+                        ),
+                    ])
+                    .finish(),
+            );
+        }
+
+        // Nodes, each with a handle on top to catch the mouse.
+        for (n, node) in self.nodes.iter().enumerate() {
+            let [x, y] = node.position;
+            builder = builder
+                .child(level::render_node(
+                    cx,
+                    node.position,
+                    bumpalo::collections::String::from_str_in(node.symbol.text(), cx.bump)
+                        .into_bump_str(),
+                    None,
+                    false,
+                    false,
+                    node.symbol.ty(),
+                ))
+                .child(
+                    circle(cx.bump)
+                        .attributes([
+                            attr("class", "editor-handle"),
+                            attr("r", "0.5"),
+                            attr("cx", bumpalo::format!(in cx.bump, "{}", x).into_bump_str()),
+                            attr("cy", bumpalo::format!(in cx.bump, "{}", y).into_bump_str()),
+                        ])
+                        .listeners([
+                            Model::listener(cx.bump, "mousedown", move |e| {
+                                let (x, y) = to_svg_coords(e.dyn_into().unwrap());
+                                crate::Msg::Editor(Msg::MouseDown(x, y, Some(n)))
+                            }),
+                            Model::listener(cx.bump, "mouseup", move |e| {
+                                let (x, y) = to_svg_coords(e.dyn_into().unwrap());
+                                crate::Msg::Editor(Msg::MouseUp(x, y, Some(n)))
+                            }),
+                        ])
+                        .finish(),
+                );
+        }
+
+        builder.finish()
+    }
+}
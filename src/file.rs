@@ -77,30 +77,56 @@ pub(crate) fn load_listener<'a>(
 
 pub(crate) fn fetch_listener<'a>(
     bump: &'a dodrio::bumpalo::Bump,
-    path: &'static str,
+    path: String,
     msg: impl 'static + Clone + FnOnce(String) -> crate::Msg,
     fail: impl 'static + Clone + FnOnce() -> crate::Msg,
 ) -> dodrio::Listener<'a> {
     Model::listener_raw(bump, "click", move |_, _, _, send_msg| {
-        let msg = msg.clone();
-        let fail = fail.clone();
-        let send_msg = send_msg.clone();
+        fetch(path.clone(), msg.clone(), fail.clone(), send_msg.clone())
+    })
+}
 
-        #[rustfmt::skip]
-        wasm_bindgen_futures::spawn_local(async move {
-            let Ok(response) = wasm_bindgen_futures::JsFuture::from(
-                web_sys::window().unwrap().fetch_with_str_and_init(path, 
-                    web_sys::RequestInit::new().cache(web_sys::RequestCache::NoCache))
-            ).await
-            else {return send_msg.send(fail()).await.unwrap()};
-            let Ok(response) = response.dyn_into::<web_sys::Response>()
-            else {return send_msg.send(fail()).await.unwrap()};
-            let Ok(promise) = response.text()
-            else {return send_msg.send(fail()).await.unwrap()};
-            let Ok(text) = wasm_bindgen_futures::JsFuture::from(promise).await
-            else {return send_msg.send(fail()).await.unwrap()};
+pub(crate) fn fetch(
+    path: String,
+    msg: impl 'static + FnOnce(String) -> crate::Msg,
+    fail: impl 'static + FnOnce() -> crate::Msg,
+    send_msg: async_channel::Sender<crate::Msg>,
+) {
+    #[rustfmt::skip]
+    wasm_bindgen_futures::spawn_local(async move {
+        let Ok(response) = wasm_bindgen_futures::JsFuture::from(
+            web_sys::window().unwrap().fetch_with_str_and_init(&path, 
+                web_sys::RequestInit::new().cache(web_sys::RequestCache::NoCache))
+        ).await
+        else {return send_msg.send(fail()).await.unwrap()};
+        let Ok(response) = response.dyn_into::<web_sys::Response>()
+        else {return send_msg.send(fail()).await.unwrap()};
+        // A missing file still gets a response, with an error page for its text.
+        if !response.ok() {return send_msg.send(fail()).await.unwrap()}
+        let Ok(promise) = response.text()
+        else {return send_msg.send(fail()).await.unwrap()};
+        let Ok(text) = wasm_bindgen_futures::JsFuture::from(promise).await
+        else {return send_msg.send(fail()).await.unwrap()};
 
-            send_msg.send(msg(text.as_string().unwrap())).await.unwrap();
-        });
-    })
+        send_msg.send(msg(text.as_string().unwrap())).await.unwrap();
+    });
+}
+
+/// The level pack to play. It's `levels.json`, unless the page was opened with `?pack=` naming another.
+/// Only packs from the same server are fetched.
+pub(crate) fn pack_path() -> String {
+    let location = web_sys::window().unwrap().location();
+    let Some(pack) = web_sys::UrlSearchParams::new_with_str(&location.search().unwrap())
+        .unwrap()
+        .get("pack")
+    else {
+        return "levels.json".to_owned();
+    };
+    match web_sys::Url::new_with_base(&pack, &location.href().unwrap()) {
+        Ok(url) if url.origin() == location.origin().unwrap() => pack,
+        _ => {
+            web_sys::console::warn_1(&format!("Ignoring level pack from elsewhere: {pack}").into());
+            "levels.json".to_owned()
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use crate::level::{
-    expression::{Expression, Type, Var},
-    LevelSpec,
+    expression::{Definition, Definitions, Equation, Expression, Rational, Rewrites, Type, Var},
+    Fnv, LevelSpec,
 };
 
 use super::*;
@@ -8,111 +8,380 @@ use ::serde::Deserialize;
 use anyhow::*;
 use serde::Serialize;
 use smallvec::SmallVec;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    rc::Rc,
+};
 
 #[derive(Deserialize)]
 pub(super) struct GameJson<'a> {
+    /// Names the pack in save files. A pack without one is named after its levels.
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    version: u32,
+    #[serde(borrow)]
+    functions: HashMap<&'a str, FunctionJson<'a>>,
+    /// Equations that levels can have the e-graph apply by itself, by the name of the level that proves them.
+    #[serde(default)]
     #[serde(borrow)]
-    functions: HashMap<&'a str, Type>,
+    rewrites: HashMap<&'a str, RewriteJson<'a>>,
     #[serde(borrow)]
     levels: HashMap<&'a str, LevelJson<'a>>,
+    #[serde(default)]
+    #[serde(borrow)]
+    book: Vec<BookPageJson<'a>>,
+    #[serde(default)]
+    languages: Vec<LanguageJson>,
+}
+
+/// A function symbol: either just its return type, or a definition as well.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FunctionJson<'a> {
+    Declared(Type),
+    Defined {
+        #[serde(rename = "type")]
+        ty: Type,
+        /// Each parameter's name and type, in order.
+        #[serde(borrow)]
+        parameters: Vec<(&'a str, Type)>,
+        /// What the function's application equals, in terms of the parameters.
+        #[serde(borrow)]
+        definition: TermJson<'a>,
+        #[serde(default)]
+        transparent: bool,
+        /// The level that states the definition, whose theorem unfolding or folding uses.
+        #[serde(default)]
+        #[serde(borrow)]
+        level: Option<&'a str>,
+    },
+}
+
+/// An expression written as a tree, like `["+", ["a", ["-", ["b"]]]]`.
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct TermJson<'a>(#[serde(borrow)] ExpressionJson<'a, Box<TermJson<'a>>>);
+
+#[derive(Deserialize)]
+struct RewriteJson<'a> {
+    #[serde(borrow)]
+    variables: HashMap<&'a str, Type>,
+    #[serde(borrow)]
+    lhs: TermJson<'a>,
+    #[serde(borrow)]
+    rhs: TermJson<'a>,
+}
+
+/// What's needed to parse levels, from the rest of the pack.
+struct Pack<'a> {
+    types: HashMap<&'a str, Type>,
+    definitions: Rc<Definitions>,
+    /// Each equation, and the index of the level that proves it.
+    rewrites: HashMap<&'a str, (usize, Equation)>,
+}
+
+/// A language the pack is translated into, besides the default.
+#[derive(Deserialize)]
+struct LanguageJson {
+    code: String,
+    name: String,
+    /// Translations of the game's own text.
+    #[serde(default)]
+    strings: HashMap<crate::locale::Text, String>,
+}
+
+/// A page of the book. Pages appear in the book in the order they're listed.
+#[derive(Deserialize)]
+struct BookPageJson<'a> {
+    id: String,
+    title: String,
+    /// The lines of the page, in markdown.
+    content: Vec<String>,
+    /// Levels that can be tried out from the page, through links to `level:name`.
+    #[serde(default)]
+    #[serde(borrow)]
+    levels: HashMap<&'a str, SandboxJson<'a>>,
+    /// The title and content in other languages, by language code.
+    #[serde(default)]
+    translations: HashMap<String, BookTranslationJson>,
+}
+
+#[derive(Deserialize)]
+struct BookTranslationJson {
+    title: String,
+    content: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SandboxJson<'a> {
+    #[serde(borrow)]
+    variables: HashMap<&'a str, Type>,
+    nodes: Vec<(ExpressionJson<'a, usize>, [f64; 2])>,
+    hypotheses: Vec<usize>,
+    conclusion: usize,
+    #[serde(default)]
+    text_box: Option<String>,
+    #[serde(default)]
+    text_box_translations: HashMap<String, String>,
+    #[serde(default)]
+    unlocks: Unlocks,
+    #[serde(default)]
+    #[serde(borrow)]
+    rewrites: Vec<&'a str>,
+    #[serde(default)]
+    evaluate: bool,
 }
 
 impl<'a> TryFrom<GameJson<'a>> for GameData {
     type Error = Error;
 
     fn try_from(json: GameJson<'a>) -> Result<Self> {
-        let indices: HashMap<&'a str, usize> = json.levels.keys().copied().zip(0..).collect();
-        let function_types = &json.functions;
+        // Serde only shows an error's outermost context, so the whole chain goes into the message.
+        parse_game(json).map_err(|err| anyhow!("{err:#}"))
+    }
+}
 
-        let levels = json
-            .levels
+fn parse_game<'a>(json: GameJson<'a>) -> Result<GameData> {
+    let indices: HashMap<&'a str, usize> = json.levels.keys().copied().zip(0..).collect();
+    let id = json.id.unwrap_or_else(|| {
+        let mut hash = Fnv::new();
+        for name in json.levels.keys().collect::<BTreeSet<_>>() {
+            hash.text(name);
+        }
+        format!("levels-{:016x}", hash.finish())
+    });
+    let mut pack = Pack::parse(json.functions, &indices)?;
+
+    // The rewrites are checked against the statements of the levels they're named after.
+    let statements = json
+        .levels
+        .iter()
+        .map(|(name, json)| {
+            json.statement(&pack)
+                .with_context(|| format!("Failed to parse level {name}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    pack.parse_rewrites(json.rewrites, &indices, &statements)?;
+    let pack = &pack;
+
+    let book = crate::book::Book::new(
+        json.book
             .into_iter()
-            .map(|(name, json)| {
-                json.parse(&indices, name.to_owned(), function_types)
-                    .with_context(|| format!("Failed to parse level {name}"))
+            .map(|page| {
+                let levels = page
+                    .levels
+                    .into_iter()
+                    .map(|(name, json)| {
+                        json.parse(pack)
+                            .map(|sandbox| (name.to_owned(), sandbox))
+                            .with_context(|| format!("Failed to parse level {name}"))
+                    })
+                    .collect::<Result<_>>()
+                    .with_context(|| format!("Failed to parse book page {}", page.id))?;
+                Ok(crate::book::PageSource {
+                    id: page.id,
+                    title: page.title,
+                    content: page.content.join("\n"),
+                    sandboxes: levels,
+                    translations: page
+                        .translations
+                        .into_iter()
+                        .map(|(language, page)| (language, (page.title, page.content.join("\n"))))
+                        .collect(),
+                })
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<Vec<_>>>()?,
+    )
+    .context("Failed to parse book")?;
+
+    let languages = json
+        .languages
+        .into_iter()
+        .map(|language| crate::locale::Language {
+            code: language.code,
+            name: language.name,
+            strings: language.strings,
+        })
+        .collect();
 
-        Ok(GameData { levels })
+    let levels = json
+        .levels
+        .into_iter()
+        .zip(statements)
+        .map(|((name, json), statement)| {
+            json.parse(&indices, name.to_owned(), statement, pack, &book)
+                .with_context(|| format!("Failed to parse level {name}"))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let data = GameData {
+        id,
+        version: json.version,
+        levels,
+        book,
+        languages,
+        functions: pack
+            .types
+            .iter()
+            .map(|(&f, &ty)| (f.to_owned(), ty))
+            .collect(),
+        definitions: pack.definitions.clone(),
+    };
+
+    // A rewrite is a theorem the level gets to use, so the level must come after it,
+    // or its proof could be circular.
+    for (ix, level) in data.levels.iter().enumerate() {
+        let earlier = data.earlier_levels(ix);
+        if let Some(&theorem) = level
+            .spec
+            .rewrite_theorems()
+            .iter()
+            .find(|theorem| !earlier.contains(theorem))
+        {
+            bail!(
+                "Level {} uses rewrite {}, which isn't one of its prereqs.",
+                level.name,
+                data.levels[theorem].name
+            );
+        }
     }
+    Ok(data)
 }
 
-#[derive(Deserialize)]
-struct LevelJson<'a> {
+/// Serialized by the level editor, which leaves out whatever is at its default.
+#[derive(Deserialize, Serialize)]
+pub(super) struct LevelJson<'a> {
     #[serde(borrow)]
     variables: HashMap<&'a str, Type>,
     nodes: Vec<(ExpressionJson<'a, usize>, [f64; 2])>,
     hypotheses: Vec<usize>,
     conclusion: usize,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "SmallVec::is_empty")]
     #[serde(borrow)]
     text_box: SmallVec<[&'a str; 2]>,
+    /// The text box's message in other languages, by language code.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    text_box_translations: HashMap<String, String>,
     map_position: [f64; 2],
     bezier_vector: [f64; 2],
     prereqs: Vec<&'a str>,
     next_level: Vec<&'a str>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_default")]
     unlocks: Unlocks,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_default")]
     axiom: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    par: Option<[u32; 2]>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    classical: bool,
+    /// The editor doesn't write tutorials.
+    #[serde(default, skip_serializing)]
+    tutorial: Vec<TutorialStepJson>,
+    /// The pack's rewrites that are active in this level.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(borrow)]
+    rewrites: Vec<&'a str>,
+    /// Whether arithmetic on numerals is done automatically in this level.
+    #[serde(default, skip_serializing_if = "is_default")]
+    evaluate: bool,
+}
+
+fn is_default<T: Default + PartialEq>(x: &T) -> bool {
+    *x == T::default()
+}
+
+#[derive(Deserialize)]
+struct TutorialStepJson {
+    message: String,
+    #[serde(default)]
+    message_translations: HashMap<String, String>,
+    #[serde(default)]
+    nodes: Vec<usize>,
+    #[serde(default)]
+    wires: Vec<usize>,
+    advance: crate::level::tutorial::Action,
 }
 
 impl<'a> LevelJson<'a> {
+    /// The level's statement, without its rewrites.
+    fn statement(&self, pack: &Pack<'a>) -> Result<LevelSpec> {
+        parse_statement(
+            &self.variables,
+            &self.nodes,
+            self.hypotheses.clone(),
+            self.conclusion,
+            pack,
+        )
+    }
+
+    /// `statement` is what `LevelJson::statement` returned.
     fn parse(
         self,
         indices: &HashMap<&'a str, usize>,
         name: String,
-        function_types: &HashMap<&'a str, Type>,
+        statement: LevelSpec,
+        pack: &Pack<'a>,
+        book: &crate::book::Book,
     ) -> Result<Level> {
         let Self {
-            variables,
+            variables: _,
             nodes,
-            hypotheses,
-            conclusion,
+            hypotheses: _,
+            conclusion: _,
             text_box,
+            text_box_translations,
             map_position,
             bezier_vector,
             prereqs,
             next_level,
             unlocks,
             axiom,
+            par,
+            classical,
+            tutorial,
+            rewrites,
+            evaluate,
         } = self;
 
-        let mut x_min = f64::INFINITY;
-        let mut y_min = f64::INFINITY;
-        let mut x_max = f64::NEG_INFINITY;
-        let mut y_max = f64::NEG_INFINITY;
+        let num_nodes = nodes.len();
+        let tutorial = tutorial
+            .into_iter()
+            .map(|step| {
+                let step = crate::level::tutorial::Step {
+                    message: crate::locale::Translated::new(
+                        step.message,
+                        step.message_translations,
+                    ),
+                    nodes: step.nodes,
+                    wires: step.wires,
+                    advance: step.advance,
+                };
+                if let Some(node) = step.referenced_nodes().find(|&node| node >= num_nodes) {
+                    bail!("Tutorial refers to node {}, which doesn't exist.", node);
+                }
+                Ok(step)
+            })
+            .collect::<Result<_>>()?;
 
-        for &(_, [x, y]) in &nodes {
-            x_min = x_min.min(x);
-            y_min = y_min.min(y);
-            x_max = x_max.max(x);
-            y_max = y_max.max(y);
-        }
+        let spec = statement.with_rewrites(pack.rewrites(&rewrites, evaluate)?);
+        let panzoom = crate::render::PanZoom::fit(nodes.iter().map(|&(_, position)| position));
 
         Ok(Level {
             name,
-            spec: LevelSpec::new(
-                nodes
-                    .into_iter()
-                    .map(|(expr, pos)| Ok((expr.parse(&variables, function_types)?, pos)))
-                    .collect::<Result<_>>()?,
-                hypotheses,
-                conclusion,
-            )?,
-            panzoom: crate::render::PanZoom {
-                svg_corners: ([x_min - 1., y_min - 1.], [x_max + 1., y_max + 3.]),
-            },
+            spec,
+            panzoom,
             text_box: match text_box.as_slice() {
                 [] => None,
-                [text] => Some((text.to_string(), None)),
+                [text] => Some((
+                    crate::locale::Translated::new(text.to_string(), text_box_translations),
+                    None,
+                )),
                 [text, page] => Some((
-                    text.to_string(),
+                    crate::locale::Translated::new(text.to_string(), text_box_translations),
                     Some(
-                        (*page)
-                            .try_into()
-                            .map_err(|_| anyhow!("Unknown book page: {}.", page))?,
+                        book.page(page)
+                            .ok_or_else(|| anyhow!("Unknown book page: {}.", page))?,
                     ),
                 )),
                 _ => bail!("Cannot parse `text_box`; array is too long."),
@@ -139,30 +408,158 @@ impl<'a> LevelJson<'a> {
                 .collect::<Result<_>>()?,
             unlocks,
             axiom,
+            par,
+            classical,
+            tutorial,
         })
     }
 }
 
-#[derive(Deserialize)]
+impl<'a> LevelJson<'a> {
+    /// A level with the statement `spec`, for the pack's author to fill in the rest of.
+    pub(super) fn export(spec: &'a LevelSpec) -> Self {
+        let mut variables = HashMap::new();
+        let nodes = spec
+            .nodes()
+            .iter()
+            .map(|(expression, position)| {
+                if let Expression::Variable(Var(v, ty)) = expression {
+                    variables.insert(v.as_str(), *ty);
+                }
+                (ExpressionJson::export(expression), *position)
+            })
+            .collect();
+        Self {
+            variables,
+            nodes,
+            hypotheses: spec.hypotheses().to_vec(),
+            conclusion: spec.conclusion(),
+            text_box: SmallVec::new(),
+            text_box_translations: HashMap::new(),
+            map_position: [0., 0.],
+            bezier_vector: [1., 0.],
+            prereqs: Vec::new(),
+            next_level: Vec::new(),
+            unlocks: Unlocks::default(),
+            axiom: false,
+            par: None,
+            classical: false,
+            tutorial: Vec::new(),
+            rewrites: Vec::new(),
+            evaluate: false,
+        }
+    }
+}
+
+impl<'a> SandboxJson<'a> {
+    fn parse(self, pack: &Pack<'a>) -> Result<crate::book::Sandbox> {
+        let Self {
+            variables,
+            nodes,
+            hypotheses,
+            conclusion,
+            text_box,
+            text_box_translations,
+            unlocks,
+            rewrites,
+            evaluate,
+        } = self;
+        let (spec, panzoom) = parse_spec(
+            variables, nodes, hypotheses, conclusion, &rewrites, evaluate, pack,
+        )?;
+        Ok(crate::book::Sandbox {
+            spec,
+            panzoom,
+            text_box: text_box
+                .map(|text_box| crate::locale::Translated::new(text_box, text_box_translations)),
+            unlocks,
+        })
+    }
+}
+
+/// Parse a level's statement, and find a view that shows all of it.
+fn parse_spec<'a>(
+    variables: HashMap<&'a str, Type>,
+    nodes: Vec<(ExpressionJson<'a, usize>, [f64; 2])>,
+    hypotheses: Vec<usize>,
+    conclusion: usize,
+    rewrites: &[&'a str],
+    evaluate: bool,
+    pack: &Pack<'a>,
+) -> Result<(LevelSpec, crate::render::PanZoom)> {
+    let panzoom = crate::render::PanZoom::fit(nodes.iter().map(|&(_, position)| position));
+    let spec = parse_statement(&variables, &nodes, hypotheses, conclusion, pack)?;
+    Ok((
+        spec.with_rewrites(pack.rewrites(rewrites, evaluate)?),
+        panzoom,
+    ))
+}
+
+/// Parse a level's statement, with no rewrites active.
+fn parse_statement<'a>(
+    variables: &HashMap<&'a str, Type>,
+    nodes: &[(ExpressionJson<'a, usize>, [f64; 2])],
+    hypotheses: Vec<usize>,
+    conclusion: usize,
+    pack: &Pack<'a>,
+) -> Result<LevelSpec> {
+    LevelSpec::new(
+        nodes
+            .iter()
+            .map(|(expr, pos)| Ok((expr.clone().parse(variables, &pack.types)?, *pos)))
+            .collect::<Result<_>>()?,
+        hypotheses,
+        conclusion,
+        pack.definitions.clone(),
+        Rewrites::default(),
+    )
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub(super) enum ExpressionJson<'a, T> {
+    /// A variable, or a numeral.
     Variable(&'a str),
+    Numeral(serde_json::Number),
     Other(&'a str, SmallVec<[T; 2]>),
 }
 
+impl<'a, T: Clone> ExpressionJson<'a, T> {
+    fn export(expression: &'a Expression<T>) -> Self {
+        match expression {
+            Expression::Variable(Var(v, _)) => ExpressionJson::Variable(v),
+            // Numerals are written as functions of no inputs, like constants.
+            expression => ExpressionJson::Other(
+                expression.text(),
+                expression.inputs().iter().cloned().collect(),
+            ),
+        }
+    }
+}
+
 impl<'a, T> ExpressionJson<'a, T> {
     fn parse(
         self,
         variable_types: &HashMap<&'a str, Type>,
         function_types: &HashMap<&'a str, Type>,
     ) -> Result<Expression<T>> {
+        let numeral = |text: String| {
+            if Rational::parse(&text).is_none() {
+                bail!("{} is not a numeral.", text);
+            }
+            Ok(Expression::Function(
+                text,
+                Type::RealNumber,
+                SmallVec::new(),
+            ))
+        };
         Ok(match self {
-            ExpressionJson::Variable(v) => Expression::Variable(Var(
-                v.to_owned(),
-                *variable_types
-                    .get(v)
-                    .ok_or(anyhow!("Variable {}'s type is not stated.", v))?,
-            )),
+            ExpressionJson::Variable(v) if !variable_types.contains_key(v) => numeral(v.to_owned())
+                .map_err(|_| anyhow!("Variable {}'s type is not stated.", v))?,
+            ExpressionJson::Variable(v) => {
+                Expression::Variable(Var(v.to_owned(), variable_types[v]))
+            }
+            ExpressionJson::Numeral(n) => numeral(n.to_string())?,
             ExpressionJson::Other("∧", inputs) => Expression::And(inputs),
             ExpressionJson::Other("∨", inputs) => Expression::Or(inputs),
             ExpressionJson::Other("⇒", inputs) => {
@@ -181,6 +578,12 @@ impl<'a, T> ExpressionJson<'a, T> {
                     )
                 })?)
             }
+            ExpressionJson::Other(f, inputs)
+                if inputs.is_empty() && !function_types.contains_key(f) =>
+            {
+                numeral(f.to_owned())
+                    .map_err(|_| anyhow!("Function {}'s return type is not stated.", f))?
+            }
             ExpressionJson::Other(f, inputs) => Expression::Function(
                 f.to_owned(),
                 *function_types
@@ -192,23 +595,450 @@ impl<'a, T> ExpressionJson<'a, T> {
     }
 }
 
+impl<'a> Pack<'a> {
+    /// The pack's functions, with no rewrites yet.
+    fn parse(
+        functions: HashMap<&'a str, FunctionJson<'a>>,
+        indices: &HashMap<&'a str, usize>,
+    ) -> Result<Self> {
+        let types = functions
+            .iter()
+            .map(|(&f, json)| match json {
+                FunctionJson::Declared(ty) | FunctionJson::Defined { ty, .. } => (f, *ty),
+            })
+            .collect();
+
+        let mut definitions = Definitions::new();
+        for (f, json) in functions {
+            if let FunctionJson::Defined {
+                ty,
+                parameters,
+                definition,
+                transparent,
+                level,
+            } = json
+            {
+                let mut definition =
+                    parse_definition(ty, parameters, definition, transparent, &types)
+                        .with_context(|| format!("Failed to parse the definition of {f}"))?;
+                definition.level = level
+                    .map(|level| {
+                        indices.get(level).copied().ok_or_else(|| {
+                            anyhow!("Unknown level {} stating the definition of {}.", level, f)
+                        })
+                    })
+                    .transpose()?;
+                definitions.insert(f.to_owned(), definition);
+            }
+        }
+
+        // A definition that refers to itself, even indirectly, could be unfolded forever.
+        fn visit<'d>(
+            f: &'d str,
+            definitions: &'d Definitions,
+            visiting: &mut Vec<&'d str>,
+            done: &mut HashSet<&'d str>,
+        ) -> Result<()> {
+            if done.contains(f) {
+                return Ok(());
+            }
+            if visiting.contains(&f) {
+                bail!("Function {} is defined in terms of itself.", f);
+            }
+            if let Some(definition) = definitions.get(f) {
+                visiting.push(f);
+                for expression in &definition.body {
+                    if let Expression::Function(g, _, _) = expression {
+                        visit(g, definitions, visiting, done)?;
+                    }
+                }
+                visiting.pop();
+            }
+            done.insert(f);
+            Ok(())
+        }
+        let mut done = HashSet::new();
+        for f in definitions.keys() {
+            visit(f, &definitions, &mut Vec::new(), &mut done)?;
+        }
+
+        Ok(Self {
+            types,
+            definitions: Rc::new(definitions),
+            rewrites: HashMap::new(),
+        })
+    }
+
+    /// Parse the rewrites, each of which must be the statement of the level it's named after,
+    /// by the level's index in `statements`.
+    fn parse_rewrites(
+        &mut self,
+        rewrites: HashMap<&'a str, RewriteJson<'a>>,
+        indices: &HashMap<&'a str, usize>,
+        statements: &[LevelSpec],
+    ) -> Result<()> {
+        for (theorem, json) in rewrites {
+            let level = *indices
+                .get(theorem)
+                .ok_or_else(|| anyhow!("Rewrite {} isn't named after a level.", theorem))?;
+            let equation = json
+                .parse(&self.types)
+                .with_context(|| format!("Failed to parse rewrite {theorem}"))?;
+            if !states(&statements[level], &equation) {
+                bail!(
+                    "Rewrite {} isn't what its level states, which is {}.",
+                    theorem,
+                    statements[level].statement()
+                );
+            }
+            self.rewrites.insert(theorem, (level, equation));
+        }
+        Ok(())
+    }
+
+    /// The rewrites a level chooses to be active.
+    fn rewrites(&self, names: &[&'a str], evaluate: bool) -> Result<Rewrites> {
+        let equations = names
+            .iter()
+            .map(|name| {
+                let (level, equation) = self
+                    .rewrites
+                    .get(name)
+                    .ok_or_else(|| anyhow!("Unknown rewrite: {}.", name))?;
+                Ok((*level, equation))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Rewrites::new(equations, evaluate))
+    }
+}
+
+impl<'a> RewriteJson<'a> {
+    fn parse(self, function_types: &HashMap<&'a str, Type>) -> Result<Equation> {
+        let mut variables = self
+            .variables
+            .into_iter()
+            .map(|(name, ty)| Var(name.to_owned(), ty))
+            .collect::<Vec<_>>();
+        variables.sort();
+
+        let mut body = Vec::new();
+        let lhs = self.lhs.flatten(&variables, function_types, &mut body)?;
+        let rhs = self.rhs.flatten(&variables, function_types, &mut body)?;
+
+        let ty_of = |ix: usize| match ix.checked_sub(variables.len()) {
+            None => variables[ix].1,
+            Some(k) => body[k].ty(),
+        };
+        for expression in &body {
+            if !expression.tycheck(|&ix| ty_of(ix)) {
+                bail!("The equation fails typechecking.");
+            }
+        }
+        if ty_of(lhs) != ty_of(rhs) {
+            bail!("The two sides of the equation have different types.");
+        }
+
+        Ok(Equation {
+            variables,
+            body,
+            lhs,
+            rhs,
+        })
+    }
+}
+
+/// Whether `spec` has no hypotheses, and concludes the equation, up to the names of its variables.
+fn states(spec: &LevelSpec, equation: &Equation) -> bool {
+    let Expression::Equal([lhs, rhs]) = &spec.nodes()[spec.conclusion()].0 else {
+        return false;
+    };
+    let mut renaming = vec![None; equation.variables.len()];
+    spec.hypotheses().is_empty()
+        && same_expression(spec, *lhs, equation, equation.lhs, &mut renaming)
+        && same_expression(spec, *rhs, equation, equation.rhs, &mut renaming)
+}
+
+/// Whether node `n` of `spec` is the expression at `ix` of `equation`.
+/// `renaming` is each of the equation's variables' name in `spec`, as far as it's known.
+fn same_expression<'s>(
+    spec: &'s LevelSpec,
+    n: usize,
+    equation: &Equation,
+    ix: usize,
+    renaming: &mut [Option<&'s str>],
+) -> bool {
+    let expression = &spec.nodes()[n].0;
+    let Some(k) = ix.checked_sub(equation.variables.len()) else {
+        let Expression::Variable(Var(name, ty)) = expression else {
+            return false;
+        };
+        return *ty == equation.variables[ix].1
+            && match renaming[ix] {
+                Some(bound) => bound == name,
+                // Two of the equation's variables can't both be renamed to the same one.
+                None if renaming.contains(&Some(name.as_str())) => false,
+                None => {
+                    renaming[ix] = Some(name);
+                    true
+                }
+            };
+    };
+
+    let pattern = &equation.body[k];
+    expression.clone().map(|_| ()) == pattern.clone().map(|_| ())
+        && expression
+            .inputs()
+            .iter()
+            .zip(pattern.inputs())
+            .all(|(&n, &ix)| same_expression(spec, n, equation, ix, renaming))
+}
+
+fn parse_definition<'a>(
+    ty: Type,
+    parameters: Vec<(&'a str, Type)>,
+    definition: TermJson<'a>,
+    transparent: bool,
+    function_types: &HashMap<&'a str, Type>,
+) -> Result<Definition> {
+    let parameters = parameters
+        .into_iter()
+        .map(|(name, ty)| Var(name.to_owned(), ty))
+        .collect::<Vec<_>>();
+
+    let mut body = Vec::new();
+    if definition.flatten(&parameters, function_types, &mut body)? < parameters.len() {
+        bail!("The definition is just one of the parameters.");
+    }
+
+    let ty_of = |ix: usize| match ix.checked_sub(parameters.len()) {
+        None => parameters[ix].1,
+        Some(k) => body[k].ty(),
+    };
+    for expression in &body {
+        if !expression.tycheck(|&ix| ty_of(ix)) {
+            bail!("The definition fails typechecking.");
+        }
+    }
+    if ty_of(parameters.len() + body.len() - 1) != ty {
+        bail!("The definition's type doesn't match the function's.");
+    }
+
+    Ok(Definition {
+        parameters,
+        body,
+        transparent,
+        level: None,
+    })
+}
+
+impl<'a> TermJson<'a> {
+    /// Add the term's subterms to `body`, in an order where inputs come first,
+    /// and return its index, in the numbering of `Definition::body`.
+    fn flatten(
+        self,
+        parameters: &[Var],
+        function_types: &HashMap<&'a str, Type>,
+        body: &mut Vec<Expression<usize>>,
+    ) -> Result<usize> {
+        // A name is a parameter, unless it's a numeral.
+        if let ExpressionJson::Variable(v) = self.0 {
+            if Rational::parse(v).is_none() {
+                return parameters
+                    .iter()
+                    .position(|Var(name, _)| name == v)
+                    .ok_or_else(|| anyhow!("{} is not a parameter.", v));
+            }
+        }
+
+        let expression = self.0.parse(&HashMap::new(), function_types)?;
+        let mut result = Ok(());
+        let expression = expression.map(|input| {
+            input
+                .flatten(parameters, function_types, body)
+                .unwrap_or_else(|e| {
+                    result = Err(e);
+                    0
+                })
+        });
+        result?;
+
+        body.push(expression);
+        Ok(parameters.len() + body.len() - 1)
+    }
+}
+
+/// The format save files are written in. `migrate` upgrades files in older formats.
+const SAVE_FORMAT: u64 = 2;
+
+/// A save file, with the progress in each pack by the pack's id.
+/// Packs other than the loaded one are kept as they are, without being parsed.
+#[derive(Serialize, Deserialize)]
+pub(super) struct SaveFileJson {
+    pub(super) format: u64,
+    pub(super) packs: BTreeMap<String, serde_json::Value>,
+}
+
+impl SaveFileJson {
+    pub(super) fn new(packs: BTreeMap<String, serde_json::Value>) -> Self {
+        Self {
+            format: SAVE_FORMAT,
+            packs,
+        }
+    }
+}
+
+/// Upgrade a save file to the current format, returning the format it was in.
+/// A save in the oldest format is assumed to be from `pack`.
+///
+/// - Format 0 was the progress in one pack, without saying which.
+/// - Format 1 held the progress in each pack, by the pack's id.
+/// - Format 2 added the fingerprint of each completed level.
+pub(super) fn migrate(json: &mut serde_json::Value, pack: &str) -> Result<u64> {
+    let original = match json.get("format") {
+        Some(format) => format
+            .as_u64()
+            .ok_or_else(|| anyhow!("The save format isn't a number."))?,
+        None if json.get("packs").is_some() => 1,
+        None => 0,
+    };
+    if original > SAVE_FORMAT {
+        bail!("The save is from a newer version of the game.");
+    }
+
+    if original < 1 {
+        *json = serde_json::json!({ "packs": { pack: json.take() } });
+    }
+    // From format 1, levels completed before fingerprints were recorded are trusted not to have changed.
+    json["format"] = SAVE_FORMAT.into();
+    Ok(original)
+}
+
+/// The progress in one pack.
 #[derive(Serialize, Deserialize)]
 pub(super) struct SaveJson<'a> {
+    /// The version of the pack. Missing from saves made before saves named their pack.
+    #[serde(default)]
+    version: Option<u32>,
+    /// The fingerprint of each completed level's statement, as it was when completed.
+    #[serde(default)]
+    #[serde(borrow)]
+    fingerprints: HashMap<&'a str, u64>,
     #[serde(borrow)]
     completed: HashSet<&'a str>,
     unlocks: Unlocks,
+    #[serde(default)]
+    #[serde(borrow)]
+    best: HashMap<&'a str, crate::level::Stats>,
+    /// For each completed level, the theorems its proof applies.
+    #[serde(default)]
+    #[serde(borrow)]
+    proofs: HashMap<&'a str, Vec<&'a str>>,
+    #[serde(default)]
+    constructive: bool,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    wire_style: crate::render::bezier::WireStyle,
 }
 
 impl<'a> SaveJson<'a> {
-    pub(super) fn to_data(&self, game_data: &GameData) -> SaveData {
+    pub(super) fn to_data(
+        &self,
+        game_data: &GameData,
+        warnings: &mut Vec<SaveWarning>,
+    ) -> SaveData {
+        if let Some(version) = self.version.filter(|&v| v != game_data.version) {
+            warnings.push(SaveWarning::Version(version));
+        }
+        let unknown = self
+            .completed
+            .iter()
+            .chain(self.best.keys())
+            .chain(self.proofs.keys())
+            .filter(|&&name| game_data.level_by_name(name).is_none())
+            .map(|&name| name.to_owned())
+            .collect::<BTreeSet<_>>();
+        if !unknown.is_empty() {
+            warnings.push(SaveWarning::UnknownLevels(unknown.into_iter().collect()));
+        }
+
+        // A level whose statement has changed needs proving again,
+        // and so does every level whose proof applied it, directly or not.
+        let mut changed = game_data
+            .levels
+            .iter()
+            .map(|level| {
+                self.fingerprints
+                    .get(level.name.as_str())
+                    .is_some_and(|&fingerprint| fingerprint != level.spec.fingerprint())
+            })
+            .collect::<Vec<_>>();
+        loop {
+            let mut done = true;
+            for (level, data) in game_data.levels.iter().enumerate() {
+                let uses_changed = self.proofs.get(data.name.as_str()).is_some_and(|theorems| {
+                    theorems.iter().any(|&name| {
+                        game_data
+                            .level_by_name(name)
+                            .is_some_and(|theorem| changed[theorem])
+                    })
+                });
+                if uses_changed && !changed[level] {
+                    changed[level] = true;
+                    done = false;
+                }
+            }
+            if done {
+                break;
+            }
+        }
+        let changed_names = game_data
+            .levels
+            .iter()
+            .zip(&changed)
+            .filter(|&(level, &changed)| changed && self.completed.contains(level.name.as_str()))
+            .map(|(level, _)| level.name.clone())
+            .collect::<Vec<_>>();
+        if !changed_names.is_empty() {
+            warnings.push(SaveWarning::ChangedLevels(changed_names));
+        }
+
         SaveData {
             unlocks: self.unlocks,
             completed: (0..game_data.num_levels())
                 .map(|level| {
-                    self.completed
-                        .contains(&game_data.levels[level].name.as_str())
+                    !changed[level]
+                        && self
+                            .completed
+                            .contains(&game_data.levels[level].name.as_str())
                 })
                 .collect(),
+            best: (0..game_data.num_levels())
+                .map(|level| {
+                    self.best
+                        .get(game_data.levels[level].name.as_str())
+                        .copied()
+                })
+                .collect(),
+            proofs: (0..game_data.num_levels())
+                .map(|level| {
+                    self.proofs
+                        .get(game_data.levels[level].name.as_str())
+                        .filter(|_| !changed[level])
+                        .map(|theorems| {
+                            theorems
+                                .iter()
+                                .filter_map(|name| {
+                                    game_data.levels.iter().position(|l| l.name == *name)
+                                })
+                                .collect()
+                        })
+                })
+                .collect(),
+            constructive: self.constructive,
+            language: self.language.clone(),
+            wire_style: self.wire_style,
+            other_packs: BTreeMap::new(),
         }
     }
 }
@@ -216,6 +1046,17 @@ impl<'a> SaveJson<'a> {
 impl SaveData {
     pub(super) fn to_json<'a>(&self, game_data: &'a GameData) -> SaveJson<'a> {
         SaveJson {
+            version: Some(game_data.version),
+            fingerprints: self
+                .completed
+                .iter()
+                .enumerate()
+                .filter(|&(_, &completed)| completed)
+                .map(|(level, _)| {
+                    let level = &game_data.levels[level];
+                    (level.name.as_str(), level.spec.fingerprint())
+                })
+                .collect(),
             completed: self
                 .completed
                 .iter()
@@ -229,6 +1070,101 @@ impl SaveData {
                 })
                 .collect(),
             unlocks: self.unlocks,
+            best: self
+                .best
+                .iter()
+                .enumerate()
+                .filter_map(|(level, best)| Some((game_data.levels[level].name.as_str(), (*best)?)))
+                .collect(),
+            proofs: self
+                .proofs
+                .iter()
+                .enumerate()
+                .filter_map(|(level, theorems)| {
+                    Some((
+                        game_data.levels[level].name.as_str(),
+                        theorems
+                            .as_ref()?
+                            .iter()
+                            .map(|&theorem| game_data.levels[theorem].name.as_str())
+                            .collect(),
+                    ))
+                })
+                .collect(),
+            constructive: self.constructive,
+            language: self.language.clone(),
+            wire_style: self.wire_style,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pack(conclusion: usize) -> String {
+        json!({
+            "functions": {},
+            "levels": {
+                "A": {
+                    "variables": { "p": "Ω" },
+                    "nodes": [["p", [0, 0]], [["⇒", [0, 0]], [0, 2]]],
+                    "hypotheses": [],
+                    "conclusion": conclusion,
+                    "map_position": [0, 0],
+                    "bezier_vector": [1, 0],
+                    "prereqs": [],
+                    "next_level": []
+                }
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn a_malformed_pack_is_an_error_saying_why() {
+        assert!(serde_json::from_str::<GameData>(&pack(1)).is_ok());
+        let err = serde_json::from_str::<GameData>(&pack(5)).err().unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Failed to parse level A: Conclusion index too large. (5 >= 2)"));
+    }
+
+    #[test]
+    fn migrates_a_save_of_one_pack() {
+        let progress = json!({ "completed": ["Intro.0"], "unlocks": [] });
+        let mut save = progress.clone();
+        assert_eq!(migrate(&mut save, "main").unwrap(), 0);
+        assert_eq!(
+            save,
+            json!({ "format": SAVE_FORMAT, "packs": { "main": progress } })
+        );
+    }
+
+    #[test]
+    fn migrates_a_save_of_several_packs() {
+        let packs = json!({ "main": { "completed": [] }, "other": { "completed": [] } });
+        let mut save = json!({ "packs": packs });
+        assert_eq!(migrate(&mut save, "main").unwrap(), 1);
+        assert_eq!(save, json!({ "format": SAVE_FORMAT, "packs": packs }));
+    }
+
+    #[test]
+    fn leaves_a_current_save_alone() {
+        let original = serde_json::to_value(SaveFileJson::new(BTreeMap::new())).unwrap();
+        let mut save = original.clone();
+        assert_eq!(migrate(&mut save, "main").unwrap(), SAVE_FORMAT);
+        assert_eq!(save, original);
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(migrate(
+            &mut json!({ "format": SAVE_FORMAT + 1, "packs": {} }),
+            "main"
+        )
+        .is_err());
+        assert!(migrate(&mut json!({ "format": "2", "packs": {} }), "main").is_err());
+    }
+}
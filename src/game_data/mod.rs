@@ -1,29 +1,71 @@
 mod json;
 use std::{
     cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
     ops::{BitOr, BitOrAssign},
+    rc::Rc,
+};
+
+use crate::level::{
+    expression::{Definitions, Type},
+    Propositional,
 };
 
 /// Any data that pertains to the game as a whole,
 /// as opposed to what the player has done in the game.
 /// In other words, to create a custom map, this is what needs to be replaced.
+/// The menu can load one from a file, and `?pack=` in the URL fetches one from the same server.
 #[derive(serde::Deserialize, Default)]
 #[serde(try_from = "json::GameJson")]
 pub struct GameData {
+    /// Identifies the pack within a save file, which can hold the progress of several packs.
+    /// Packs that don't give one get one from the names of their levels, so they don't share progress.
+    id: String,
+    /// Changed by the pack's author when levels change in ways old progress doesn't fit.
+    version: u32,
     levels: Vec<Level>,
+    book: crate::book::Book,
+    /// Languages other than the default, which the pack is translated into.
+    languages: Vec<crate::locale::Language>,
+    /// The pack's function symbols, and their return types.
+    functions: BTreeMap<String, Type>,
+    definitions: Rc<Definitions>,
 }
 
 pub struct Level {
     name: String,
     pub spec: crate::level::LevelSpec,
     pub panzoom: crate::render::PanZoom,
-    text_box: Option<(String, Option<crate::book::BookPage>)>,
+    text_box: Option<(crate::locale::Translated, Option<crate::book::BookPage>)>,
     pub map_position: [f64; 2],
     pub bezier_vector: [f64; 2],
     pub prereqs: Vec<usize>,
     pub next_level: Vec<usize>,
     pub unlocks: Unlocks,
     pub axiom: bool,
+    /// The most moves that still earn three stars, and two stars, respectively.
+    pub par: Option<[u32; 2]>,
+    /// Whether this level is classical logic, and so unavailable in constructive mode.
+    pub classical: bool,
+    tutorial: Vec<crate::level::tutorial::Step>,
+}
+
+impl Level {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How many stars (from one to three) a result earns, if this level has par values.
+    pub fn stars(&self, stats: &crate::level::Stats) -> Option<u8> {
+        let [three, two] = self.par?;
+        Some(if stats.moves() <= three {
+            3
+        } else if stats.moves() <= two {
+            2
+        } else {
+            1
+        })
+    }
 }
 
 impl GameData {
@@ -35,30 +77,185 @@ impl GameData {
         &self.levels[level]
     }
 
+    pub fn book(&self) -> &crate::book::Book {
+        &self.book
+    }
+
+    pub fn languages(&self) -> &[crate::locale::Language] {
+        &self.languages
+    }
+
+    pub fn functions(&self) -> &BTreeMap<String, Type> {
+        &self.functions
+    }
+
+    pub fn definitions(&self) -> &Rc<Definitions> {
+        &self.definitions
+    }
+
+    /// Warnings for the pack's author about levels whose conclusions don't follow from their
+    /// hypotheses by propositional logic, even taking every equation to be true,
+    /// so that a mistyped `conclusion` doesn't go unnoticed.
+    /// Axioms aren't checked, since they aren't proven.
+    pub fn check_levels(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (ix, level) in self.levels.iter().enumerate() {
+            if level.axiom {
+                continue;
+            }
+            let result = level.spec.propositional_with_atoms_true();
+            let assignment = match &result {
+                Propositional::Tautology => continue,
+                Propositional::TooLarge(atoms) => {
+                    warnings.push(format!(
+                        "Level {} has too many atoms ({atoms}) to check that it's a tautology.",
+                        level.name
+                    ));
+                    continue;
+                }
+                Propositional::Counterexample(assignment) => assignment,
+            };
+            let counterexample = result.counterexample().unwrap_or_default();
+
+            // The theorems proven on the way to this level that could supply what's missing.
+            let failing = assignment
+                .iter()
+                .filter(|(_, value)| !value)
+                .map(|(atom, _)| atom.as_str())
+                .collect::<Vec<_>>();
+            let theorems = self
+                .earlier_levels(ix)
+                .into_iter()
+                .filter(|&theorem| {
+                    let spec = &self.levels[theorem].spec;
+                    failing.iter().any(|atom| spec.mentions(atom))
+                })
+                .map(|theorem| self.levels[theorem].name.as_str())
+                .collect::<Vec<_>>();
+
+            warnings.push(if theorems.is_empty() {
+                format!(
+                    "Level {} is not a propositional tautology ({counterexample}), \
+                     and no earlier theorem mentions {}. Is its conclusion right?",
+                    level.name,
+                    failing.join(", ")
+                )
+            } else {
+                format!(
+                    "Level {} is not a propositional tautology ({counterexample}); \
+                     needs theorems {}.",
+                    level.name,
+                    theorems.join(", ")
+                )
+            });
+        }
+        warnings
+    }
+
+    /// The levels that must be completed before `level` can be, following `prereqs` back.
+    fn earlier_levels(&self, level: usize) -> BTreeSet<usize> {
+        let mut earlier = BTreeSet::new();
+        let mut stack = self.levels[level].prereqs.clone();
+        while let Some(prereq) = stack.pop() {
+            if earlier.insert(prereq) {
+                stack.extend(&self.levels[prereq].prereqs);
+            }
+        }
+        earlier
+    }
+
+    /// The game's text in a language, falling back to the default if the pack doesn't have it.
+    pub fn strings(&self, language: Option<&str>) -> crate::locale::Strings<'_> {
+        crate::locale::Strings::new(
+            self.languages
+                .iter()
+                .find(|l| Some(l.code.as_str()) == language),
+        )
+    }
+
+    pub fn level_by_name(&self, name: &str) -> Option<usize> {
+        self.levels.iter().position(|level| level.name == name)
+    }
+
     pub fn load(&self, level: usize, global_unlocks: Unlocks) -> crate::level::State {
         let Level {
             spec,
             panzoom: pan_zoom,
             text_box,
             axiom,
+            tutorial,
             ..
         } = self.level(level);
         crate::level::State::new(
             spec,
             *pan_zoom,
-            text_box.clone(),
+            text_box
+                .as_ref()
+                .map(|(text, page)| (text.clone(), page.map(|page| self.book.id(page).to_owned()))),
+            self.book.first().map(|page| self.book.id(page).to_owned()),
+            tutorial.clone(),
             global_unlocks | self.level(level).unlocks,
             *axiom,
         )
     }
 }
 
+/// A level with the statement `spec`, written the way `levels.json` writes levels.
+/// Its place on the map and its links to other levels are left for the author to fill in.
+pub fn export_level(spec: &crate::level::LevelSpec) -> String {
+    serde_json::to_string_pretty(&json::LevelJson::export(spec)).unwrap()
+}
+
 /// Data describing what the player has done in the game.
 /// In other words, this is what the save/load game buttons manipulate.
 #[derive(Default)]
 pub struct SaveData {
     unlocks: Unlocks,
     completed: Vec<bool>,
+    /// The best result the player has achieved in each level.
+    best: Vec<Option<crate::level::Stats>>,
+    /// The theorems applied in the most recent proof of each level.
+    /// `None` if the level has not been completed, or was completed before proofs were recorded.
+    proofs: Vec<Option<Vec<usize>>>,
+    /// In constructive mode, only theorems proven without classical logic may be applied.
+    constructive: bool,
+    /// The code of the chosen language, or `None` for the default.
+    language: Option<String>,
+    wire_style: crate::render::bezier::WireStyle,
+    /// The progress of other packs in the same save file, by their ids, kept as it was.
+    other_packs: BTreeMap<String, serde_json::Value>,
+}
+
+/// Ways a loaded save file might not fit the loaded pack.
+pub enum SaveWarning {
+    /// The save file has no progress in this pack, only in the packs listed.
+    /// The progress so far is kept.
+    OtherPacks(Vec<String>),
+    /// The save file is from before saves named their pack, so it's assumed to be from this one.
+    Untagged,
+    /// The progress is from another version of the pack.
+    Version(u32),
+    /// Levels the save mentions that the pack doesn't have. Their progress is dropped.
+    UnknownLevels(Vec<String>),
+    /// Completed levels whose statements have changed since, or whose proofs applied theorems that have,
+    /// so need proving again.
+    ChangedLevels(Vec<String>),
+}
+
+/// The result of tracing a level's proof back to the axioms.
+pub struct Audit {
+    pub axioms: BTreeSet<usize>,
+    /// Completed levels reached along the way, whose proofs were never recorded.
+    pub unrecorded: BTreeSet<usize>,
+    /// Levels reached along the way that are tagged as classical.
+    pub classical: BTreeSet<usize>,
+}
+
+impl Audit {
+    /// Whether the proof is known to avoid classical logic.
+    pub fn constructive(&self) -> bool {
+        self.classical.is_empty() && self.unrecorded.is_empty()
+    }
 }
 
 impl SaveData {
@@ -66,16 +263,44 @@ impl SaveData {
         Self {
             unlocks: Unlocks::NONE,
             completed: vec![false; game_data.num_levels()],
+            best: vec![None; game_data.num_levels()],
+            proofs: vec![None; game_data.num_levels()],
+            constructive: false,
+            language: None,
+            wire_style: Default::default(),
+            other_packs: BTreeMap::new(),
         }
     }
 
     pub fn save(&self, game_data: &GameData) -> String {
-        serde_json::to_string(&self.to_json(game_data)).unwrap()
+        let mut packs = self.other_packs.clone();
+        packs.insert(
+            game_data.id.clone(),
+            serde_json::to_value(self.to_json(game_data)).unwrap(),
+        );
+        serde_json::to_string(&json::SaveFileJson::new(packs)).unwrap()
     }
 
-    pub fn load(game_data: &GameData, json: &str) -> Result<Self, serde_json::Error> {
-        let json: json::SaveJson = serde_json::from_str(json)?;
-        Ok(json.to_data(game_data))
+    /// Replace the progress with that of a save file, and say how the save didn't fit the pack.
+    pub fn load(&mut self, game_data: &GameData, json: &str) -> anyhow::Result<Vec<SaveWarning>> {
+        let mut warnings = Vec::new();
+        let mut json: serde_json::Value = serde_json::from_str(json)?;
+        if json::migrate(&mut json, &game_data.id)? == 0 {
+            warnings.push(SaveWarning::Untagged);
+        }
+        let mut file: json::SaveFileJson = serde_json::from_value(json)?;
+        let progress = file.packs.remove(&game_data.id);
+        let packs = file.packs;
+
+        match progress {
+            Some(progress) => {
+                let progress = <json::SaveJson as serde::Deserialize>::deserialize(&progress)?;
+                *self = progress.to_data(game_data, &mut warnings);
+            }
+            None => warnings.push(SaveWarning::OtherPacks(packs.keys().cloned().collect())),
+        }
+        self.other_packs = packs;
+        Ok(warnings)
     }
 
     pub fn completed(&self, level: usize) -> bool {
@@ -91,6 +316,99 @@ impl SaveData {
         !std::mem::replace(&mut self.completed[level], true)
     }
 
+    pub fn best(&self, level: usize) -> Option<&crate::level::Stats> {
+        self.best[level].as_ref()
+    }
+
+    /// Returns whether the save data has changed.
+    pub fn record_stats(&mut self, level: usize, stats: crate::level::Stats) -> bool {
+        match &mut self.best[level] {
+            Some(best) if !stats.better_than(best) => false,
+            best => {
+                *best = Some(stats);
+                true
+            }
+        }
+    }
+
+    /// Returns whether the save data has changed.
+    pub fn record_proof(&mut self, level: usize, theorems: &[usize]) -> bool {
+        if self.proofs[level].as_deref() == Some(theorems) {
+            false
+        } else {
+            self.proofs[level] = Some(theorems.to_vec());
+            true
+        }
+    }
+
+    /// Every axiom that the proof of `level` ultimately relies on, like Lean's `#print axioms`.
+    pub fn audit(&self, game_data: &GameData, level: usize) -> Audit {
+        let mut audit = Audit {
+            axioms: BTreeSet::new(),
+            unrecorded: BTreeSet::new(),
+            classical: BTreeSet::new(),
+        };
+        let mut visited = BTreeSet::new();
+        let mut work = vec![level];
+        while let Some(level) = work.pop() {
+            if !visited.insert(level) {
+                continue;
+            }
+            if game_data.level(level).classical {
+                audit.classical.insert(level);
+            }
+            if game_data.level(level).axiom {
+                audit.axioms.insert(level);
+            } else if let Some(theorems) = &self.proofs[level] {
+                work.extend(theorems);
+            } else {
+                audit.unrecorded.insert(level);
+            }
+        }
+        audit
+    }
+
+    /// Whether the player may apply the theorem proven in `level`.
+    pub fn theorem_available(&self, game_data: &GameData, level: usize) -> bool {
+        self.completed(level) && (!self.constructive || self.audit(game_data, level).constructive())
+    }
+
+    pub fn constructive(&self) -> bool {
+        self.constructive
+    }
+
+    pub fn toggle_constructive(&mut self) {
+        self.constructive = !self.constructive;
+    }
+
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    /// Switch to the next of the pack's languages, going back to the default after the last.
+    pub fn next_language(&mut self, game_data: &GameData) {
+        let current = game_data
+            .languages
+            .iter()
+            .position(|l| Some(l.code.as_str()) == self.language());
+        self.language = game_data
+            .languages
+            .get(current.map_or(0, |i| i + 1))
+            .map(|l| l.code.clone());
+    }
+
+    pub fn wire_style(&self) -> crate::render::bezier::WireStyle {
+        self.wire_style
+    }
+
+    pub fn toggle_wire_style(&mut self) {
+        use crate::render::bezier::WireStyle;
+        self.wire_style = match self.wire_style {
+            WireStyle::Curved => WireStyle::Routed,
+            WireStyle::Routed => WireStyle::Curved,
+        };
+    }
+
     pub fn unlocks(&self) -> Unlocks {
         self.unlocks
     }
@@ -0,0 +1,148 @@
+//! Searching for values that make every fact of a case hold but its goal fail,
+//! which shows that the goal can't be proven from the facts alone.
+//!
+//! Truth-valued variables and applications of undefined functions are assigned truth values at random,
+//! and real variables small rational values. Numerals, the basic operations on them,
+//! and the pack's defined functions are evaluated. Nodes known to be equal must get equal values.
+
+use std::collections::HashMap;
+
+use super::{Case, Node};
+use crate::level::expression::{Expression, Rational, Type};
+
+/// How many assignments are tried before giving up.
+const TRIALS: u32 = 1000;
+
+/// The real values that variables are sampled from.
+const SAMPLES: &[&str] = &["0", "1", "-1", "2", "-2", "3", "1/2", "-1/2", "1/3", "10"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Value {
+    Truth(bool),
+    Number(Rational),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Truth(true) => write!(f, "⊤"),
+            Value::Truth(false) => write!(f, "⊥"),
+            Value::Number(x) => write!(f, "{}", x.numeral()),
+        }
+    }
+}
+
+/// A value for every node of a case, by node index.
+#[derive(Debug, Clone)]
+pub struct Counterexample {
+    values: Vec<Value>,
+}
+
+impl Counterexample {
+    pub fn value(&self, node: Node) -> Value {
+        self.values[node.0]
+    }
+}
+
+/// A xorshift generator, seeded the same every time, so that a search gives the same answer when repeated.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn sample(&mut self, ty: Type, samples: &[Rational]) -> Value {
+        let n = self.next();
+        match ty {
+            Type::TruthValue => Value::Truth(n & 1 == 1),
+            Type::RealNumber => Value::Number(samples[(n % samples.len() as u64) as usize]),
+        }
+    }
+}
+
+impl Case {
+    /// Search for values that make every proven node true, and the goal false.
+    pub fn counterexample(&self) -> Option<Counterexample> {
+        let samples = SAMPLES
+            .iter()
+            .map(|x| Rational::parse(x).expect("Samples are numerals."))
+            .collect::<Vec<_>>();
+        let classes = self.egg.classes();
+        let goal = self.goal().0 .0;
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+
+        (0..TRIALS).find_map(|_| {
+            // The values of variables and undefined functions, by their inputs' values.
+            let mut atoms = HashMap::new();
+            let mut values: Vec<Value> = Vec::with_capacity(self.nodes.len());
+            for data in &self.nodes {
+                let expression = data.expression.clone().map(|input| values[input.0 .0]);
+                let value = self.evaluate(expression, &mut atoms, &mut random, &samples);
+                values.push(value);
+            }
+
+            let mut class_values = HashMap::new();
+            let consistent = classes
+                .iter()
+                .zip(&values)
+                .all(|(class, value)| *class_values.entry(class).or_insert(value) == value);
+            let facts_hold = self
+                .nodes
+                .iter()
+                .zip(&values)
+                .all(|(data, value)| !data.proven || *value == Value::Truth(true));
+            (consistent && facts_hold && values[goal] == Value::Truth(false))
+                .then_some(Counterexample { values })
+        })
+    }
+
+    fn evaluate(
+        &self,
+        expression: Expression<Value>,
+        atoms: &mut HashMap<Expression<Value>, Value>,
+        random: &mut Random,
+        samples: &[Rational],
+    ) -> Value {
+        let truth = |value: &Value| *value == Value::Truth(true);
+        match &expression {
+            Expression::And(inputs) => return Value::Truth(inputs.iter().all(truth)),
+            Expression::Or(inputs) => return Value::Truth(inputs.iter().any(truth)),
+            Expression::Implies([a, b]) => return Value::Truth(!truth(a) || truth(b)),
+            Expression::Equal([a, b]) => return Value::Truth(a == b),
+            Expression::Variable(_) => {}
+            Expression::Function(f, ty, inputs) => {
+                if let Some(definition) = self.definitions.get(f) {
+                    if definition.parameters.len() == inputs.len() {
+                        let mut values = inputs.to_vec();
+                        for body in &definition.body {
+                            let body = body.clone().map(|input| values[input]);
+                            let value = self.evaluate(body, atoms, random, samples);
+                            values.push(value);
+                        }
+                        return *values.last().unwrap();
+                    }
+                }
+                let numbers = inputs
+                    .iter()
+                    .map(|input| match input {
+                        Value::Number(x) => Some(*x),
+                        Value::Truth(_) => None,
+                    })
+                    .collect::<Option<Vec<_>>>();
+                if let (Type::RealNumber, Some(numbers)) = (ty, numbers) {
+                    if let Some(value) = Rational::apply(f, &numbers) {
+                        return Value::Number(value);
+                    }
+                }
+            }
+        }
+        let ty = expression.ty();
+        *atoms
+            .entry(expression)
+            .or_insert_with(|| random.sample(ty, samples))
+    }
+}
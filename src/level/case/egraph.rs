@@ -0,0 +1,310 @@
+// Cloning an `egg::EGraph` copies the whole thing, so a case split used to cost as much as the case.
+// Instead, each case keeps a persistent log of what it has done to the e-graph,
+// and the e-graph itself is built from the log only when it's needed.
+//
+// Cases cloned from each other share one built e-graph, for as long as none of them changes it.
+// When a case needs operations the e-graph is missing, it replays just those:
+// in place if it's the only case holding the e-graph, and otherwise on a copy,
+// leaving the others the e-graph for their own state.
+// So the case at each branch of the case tree keeps its e-graph,
+// and going back to it needs nothing rebuilt, while a case split off copies it only once it changes something.
+//
+// Uses of transparently defined functions are unfolded as they're added,
+// so that they're equivalent to their definitions.
+// The level's rewrites are run over the e-graph before it's queried, whenever it has changed.
+
+use std::{cell::RefCell, rc::Rc};
+
+use super::super::expression::{Arithmetic, Definitions, Expression, Rewrites};
+
+#[derive(Clone)]
+pub struct EGraph {
+    log: Option<Rc<Log>>,
+    built: RefCell<Rc<RefCell<Built>>>,
+    rewrites: Rewrites,
+}
+
+enum Op {
+    /// Add the expression of the next node. Inputs are given by node index.
+    Add(Expression<usize>),
+    Union(usize, usize),
+}
+
+struct Log {
+    op: Op,
+    /// The number of operations, including this one.
+    len: usize,
+    prev: Option<Rc<Log>>,
+}
+
+#[derive(Clone)]
+pub struct Built {
+    pub egraph: egg::EGraph<Expression<egg::Id>, Arithmetic>,
+    pub node_to_egg: Vec<egg::Id>,
+    /// The log this e-graph was built from.
+    log: Option<Rc<Log>>,
+    definitions: Rc<Definitions>,
+    rewrites: Rewrites,
+    /// Whether the rewrites have been run since the last change.
+    saturated: bool,
+}
+
+impl EGraph {
+    pub fn new(definitions: Rc<Definitions>, rewrites: Rewrites) -> Self {
+        Self {
+            log: None,
+            built: RefCell::new(Rc::new(RefCell::new(Built {
+                egraph: egg::EGraph::new(rewrites.analysis()),
+                node_to_egg: Vec::new(),
+                log: None,
+                definitions,
+                rewrites: rewrites.clone(),
+                saturated: true,
+            }))),
+            rewrites,
+        }
+    }
+
+    pub fn rewrites(&self) -> &Rewrites {
+        &self.rewrites
+    }
+
+    fn push(&mut self, op: Op) {
+        self.log = Some(Rc::new(Log {
+            op,
+            len: len(&self.log) + 1,
+            prev: self.log.take(),
+        }));
+    }
+
+    /// Add the expression of the next node.
+    pub fn add(&mut self, expression: Expression<usize>) {
+        self.push(Op::Add(expression));
+    }
+
+    pub fn union(&mut self, n1: usize, n2: usize) {
+        self.push(Op::Union(n1, n2));
+    }
+
+    /// Whether the expressions of two nodes are known to be equal.
+    pub fn equiv(&self, n1: usize, n2: usize) -> bool {
+        self.with_built(|built| {
+            built.prepare();
+            built.egraph.find(built.node_to_egg[n1]) == built.egraph.find(built.node_to_egg[n2])
+        })
+    }
+
+    /// The e-class of each node, so that nodes with the same one are known to be equal.
+    pub fn classes(&self) -> Vec<egg::Id> {
+        self.with_built(|built| {
+            built.prepare();
+            built
+                .node_to_egg
+                .iter()
+                .map(|&id| built.egraph.find(id))
+                .collect()
+        })
+    }
+
+    /// Match a pattern against a node's expression.
+    /// For each way it matches, gives the nodes whose expressions each of `vars` matched.
+    pub fn search(
+        &self,
+        pattern: &egg::Pattern<Expression<egg::Id>>,
+        node: usize,
+        vars: &[egg::Var],
+    ) -> Vec<Vec<Vec<usize>>> {
+        use egg::Searcher;
+        self.with_built(|built| {
+            built.prepare();
+            let class = built.egraph.find(built.node_to_egg[node]);
+            let Some(matches) = pattern.search_eclass(&built.egraph, class) else {
+                return Vec::new();
+            };
+            matches
+                .substs
+                .iter()
+                .map(|subst| {
+                    vars.iter()
+                        .map(|var| match subst.get(*var) {
+                            Some(&id) => {
+                                let id = built.egraph.find(id);
+                                (0..built.node_to_egg.len())
+                                    .filter(|&n| built.egraph.find(built.node_to_egg[n]) == id)
+                                    .collect()
+                            }
+                            None => Vec::new(),
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+    }
+
+    /// Run `f` on the e-graph, brought up to date with this case.
+    pub fn with_built<R>(&self, f: impl FnOnce(&mut Built) -> R) -> R {
+        let mut held = self.built.borrow_mut();
+        let ops = self.missing_since(&held.borrow().log).expect(
+            "An e-graph only moves on while one case holds it, so it's never ahead of a case.",
+        );
+        if !ops.is_empty() {
+            if Rc::strong_count(&held) > 1 {
+                let copy = held.borrow().clone();
+                *held = Rc::new(RefCell::new(copy));
+            }
+            let mut built = held.borrow_mut();
+            for op in ops.into_iter().rev() {
+                built.apply(op);
+            }
+            built.log = self.log.clone();
+        }
+        let built = held.clone();
+        drop(held);
+
+        let result = f(&mut built.borrow_mut());
+        result
+    }
+
+    /// The operations in this log that come after `base`, newest first,
+    /// or `None` if `base` isn't an earlier version of this log.
+    fn missing_since(&self, base: &Option<Rc<Log>>) -> Option<Vec<&Op>> {
+        let mut ops = Vec::new();
+        let mut log = &self.log;
+        while len(log) > len(base) {
+            let entry = log.as_ref().unwrap();
+            ops.push(&entry.op);
+            log = &entry.prev;
+        }
+        match (log, base) {
+            (None, None) => Some(ops),
+            (Some(log), Some(base)) if Rc::ptr_eq(log, base) => Some(ops),
+            _ => None,
+        }
+    }
+}
+
+impl Built {
+    /// Run the rewrites if anything has changed, and restore the e-graph's invariants, so it can be queried.
+    fn prepare(&mut self) {
+        if !self.saturated {
+            self.rewrites.saturate(&mut self.egraph);
+            self.saturated = true;
+        }
+        if !self.egraph.clean {
+            self.egraph.rebuild();
+        }
+        assert!(self.egraph.clean);
+    }
+
+    fn apply(&mut self, op: &Op) {
+        self.saturated = false;
+        match op {
+            Op::Add(expression) => {
+                let id = self.add(expression.clone().map(|n| self.node_to_egg[n]));
+                self.node_to_egg.push(id);
+            }
+            &Op::Union(n1, n2) => {
+                self.egraph
+                    .union(self.node_to_egg[n1], self.node_to_egg[n2]);
+            }
+        }
+    }
+
+    /// Add an expression, unfolding it if it uses a transparent definition.
+    /// Definitions are checked not to refer to themselves when the pack loads, so this terminates.
+    fn add(&mut self, expression: Expression<egg::Id>) -> egg::Id {
+        let definitions = self.definitions.clone();
+        let definition = match &expression {
+            Expression::Function(f, _, args) => definitions
+                .get(f)
+                .filter(|d| d.transparent && d.parameters.len() == args.len()),
+            _ => None,
+        };
+
+        let unfolded = definition.map(|definition| {
+            let mut ids = expression.inputs().to_vec();
+            for body in &definition.body {
+                let id = self.add(body.clone().map(|ix| ids[ix]));
+                ids.push(id);
+            }
+            *ids.last().unwrap()
+        });
+
+        let id = self.egraph.add(expression);
+        if let Some(unfolded) = unfolded {
+            self.egraph.union(id, unfolded);
+        }
+        id
+    }
+}
+
+fn len(log: &Option<Rc<Log>>) -> usize {
+    log.as_ref().map_or(0, |log| log.len)
+}
+
+// Dropping a long log recursively could overflow the stack.
+impl Drop for Log {
+    fn drop(&mut self) {
+        let mut prev = self.prev.take();
+        while let Some(log) = prev {
+            prev = Rc::try_unwrap(log).ok().and_then(|mut log| log.prev.take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::expression::{Type, Var};
+
+    fn egraph(vars: usize) -> EGraph {
+        let mut egraph = EGraph::new(Rc::new(Definitions::new()), Rewrites::default());
+        for v in 0..vars {
+            egraph.add(Expression::Variable(Var(v.to_string(), Type::TruthValue)));
+        }
+        egraph
+    }
+
+    /// Which e-graph a case holds, without holding on to it.
+    fn built(egraph: &EGraph) -> *const RefCell<Built> {
+        Rc::as_ptr(&egraph.built.borrow())
+    }
+
+    #[test]
+    fn a_case_on_its_own_changes_its_e_graph_in_place() {
+        let mut case = egraph(3);
+        assert!(!case.equiv(0, 1));
+        let before = built(&case);
+        case.union(0, 1);
+        assert!(case.equiv(0, 1));
+        assert_eq!(before, built(&case));
+    }
+
+    #[test]
+    fn a_split_keeps_the_e_graph_for_the_case_split() {
+        let parent = egraph(3);
+        assert!(!parent.equiv(0, 1));
+        let mut children = [parent.clone(), parent.clone()];
+        children[0].union(0, 1);
+        children[1].union(1, 2);
+        assert!(children[0].equiv(0, 1) && !children[0].equiv(1, 2));
+        assert!(children[1].equiv(1, 2) && !children[1].equiv(0, 1));
+
+        // Going back to the case split rebuilds nothing.
+        let kept = built(&parent);
+        assert!(!parent.equiv(0, 1) && !parent.equiv(1, 2));
+        assert_eq!(kept, built(&parent));
+    }
+
+    #[test]
+    fn cases_share_an_e_graph_until_one_changes() {
+        let parent = egraph(2);
+        assert!(!parent.equiv(0, 1));
+        let mut child = parent.clone();
+        assert!(!child.equiv(0, 1));
+        assert_eq!(built(&parent), built(&child));
+        child.union(0, 1);
+        assert!(child.equiv(0, 1));
+        assert_ne!(built(&parent), built(&child));
+    }
+}
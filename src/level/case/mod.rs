@@ -1,12 +1,20 @@
+mod counterexample;
+mod egraph;
 mod render;
 mod spec;
 mod union_find;
 
-pub use spec::LevelSpec;
+pub use counterexample::Counterexample;
+pub(crate) use render::{render_node, render_wire, Part};
+pub(crate) use spec::Fnv;
+pub use spec::{LevelSpec, Propositional};
 
-use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
 
-use super::expression::{Expression, Type};
+use super::expression::{pattern_var, Definitions, Expression, Rewrites, Type, Var};
 use union_find::UnionFind;
 
 /// This is a safety feature.
@@ -29,6 +37,24 @@ impl ValidityReason {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Node(usize);
 
+impl Node {
+    /// The node made from the `ix`th node of a `LevelSpec`,
+    /// in any case descended from one built by `LevelSpec::to_case`.
+    pub fn from_spec(ix: usize) -> Self {
+        Self(ix)
+    }
+}
+
+impl union_find::Key for Node {
+    fn index(self) -> usize {
+        self.0
+    }
+
+    fn from_index(index: usize) -> Self {
+        Self(index)
+    }
+}
+
 /// Wires are identified with the nodes they emerge from.
 /// By using the `Wire` type, you are signifying that
 /// wires emerging from different nodes, but that connect together,
@@ -36,16 +62,18 @@ pub struct Node(usize);
 #[derive(Debug, Clone, Copy)]
 pub struct Wire(Node);
 
+/// Every part of a case is persistent, so cloning one to split it
+/// shares everything with the original, and later changes copy only what they touch.
 #[derive(Clone)]
 pub struct Case {
-    nodes: Vec<Data>,
+    nodes: im_rc::Vector<Data>,
     // Two nodes are in the same equivalence class iff their output wires are connected.
-    connections: Rc<UnionFind<Node>>,
+    connections: UnionFind<Node>,
     goal: Option<Wire>,
+    definitions: Rc<Definitions>,
 
     // Keeps track of which nodes describe identical expressions, even if they're displayed separately.
-    egg: RefCell<egg::EGraph<Expression<egg::Id>, ()>>,
-    node_to_egg: Vec<egg::Id>,
+    egg: egraph::EGraph,
 }
 
 #[derive(Debug, Clone)]
@@ -60,16 +88,26 @@ struct Data {
 }
 
 impl Case {
-    pub fn new() -> Self {
+    pub fn new(definitions: Rc<Definitions>, rewrites: Rewrites) -> Self {
         Self {
-            nodes: Vec::new(),
-            connections: Rc::new(UnionFind::new()),
+            nodes: im_rc::Vector::new(),
+            connections: UnionFind::new(),
             goal: None,
-            egg: RefCell::new(egg::EGraph::new(())),
-            node_to_egg: Vec::new(),
+            egg: egraph::EGraph::new(definitions.clone(), rewrites),
+            definitions,
         }
     }
 
+    /// The level pack's defined function symbols.
+    pub fn definitions(&self) -> &Definitions {
+        &self.definitions
+    }
+
+    /// The levels whose theorems `wire_equiv` may use, through the level's rewrites.
+    pub fn rewrite_theorems(&self) -> &[usize] {
+        self.egg.rewrites().theorems()
+    }
+
     pub fn ty(&self, w: Wire) -> Type {
         self.node_expression(w.0).ty()
     }
@@ -85,17 +123,13 @@ impl Case {
 
     pub fn make_node(&mut self, expression: Expression<Wire>, position: [f64; 2]) -> Node {
         let n = Node(self.nodes.len());
-        self.nodes.push(Data {
+        self.nodes.push_back(Data {
             expression: expression.clone(),
             position,
             proven: false,
             deleted: false,
         });
-        self.node_to_egg.push(
-            self.egg
-                .borrow_mut()
-                .add(expression.map(|node| self.node_to_egg[node.0 .0])),
-        );
+        self.egg.add(expression.map(|node| node.0 .0));
         n
     }
 
@@ -107,6 +141,17 @@ impl Case {
         &self.nodes[n.0].expression
     }
 
+    /// The expression carried by the wire, written out in full.
+    pub fn formula(&self, w: Wire) -> String {
+        self.node_expression(w.0).formula(|&input| {
+            if self.node_expression(input.0).inputs().is_empty() {
+                self.formula(input)
+            } else {
+                format!("({})", self.formula(input))
+            }
+        })
+    }
+
     pub fn wire_inputs(&self, w: Wire) -> impl Iterator<Item = Node> + '_ {
         self.connections
             .iter_class(w.0)
@@ -120,14 +165,61 @@ impl Case {
     /// Test whether the wires describe the same *expression*.
     /// For instance, if there are two copies of `a` on screen,
     /// `wire_equiv` will say they are equal, while `wire_eq` will not.
+    /// It also sees through transparent definitions, and applies the level's rewrites.
     pub fn wire_equiv(&self, w1: Wire, w2: Wire) -> bool {
-        let mut egg = self.egg.borrow_mut();
-        if !egg.clean {
-            egg.rebuild();
-        }
-        assert!(egg.clean);
+        self.egg.equiv(w1.0 .0, w2.0 .0)
+    }
+
+    /// Match the conclusion or a hypothesis of a theorem against a node's expression,
+    /// and find nodes for as many of the theorem's variables as possible, preferring the match that finds the most.
+    pub fn match_theorem(
+        &self,
+        spec: &LevelSpec,
+        patterns: impl Iterator<Item = egg::Pattern<Expression<egg::Id>>>,
+        node: Node,
+    ) -> HashMap<Var, Node> {
+        let vars = spec.vars().collect::<Vec<_>>();
+        let pattern_vars = vars.iter().map(pattern_var).collect::<Vec<_>>();
+
+        patterns
+            .flat_map(|pattern| self.egg.search(&pattern, node.0, &pattern_vars))
+            .map(|matched| {
+                vars.iter()
+                    .zip(matched)
+                    .filter_map(|(v, nodes)| {
+                        let node = nodes.into_iter().map(Node).find(|&n| {
+                            !self.nodes[n.0].deleted && self.ty(self.node_output(n)) == v.1
+                        })?;
+                        Some((v.clone(), node))
+                    })
+                    .collect::<HashMap<_, _>>()
+            })
+            .max_by_key(HashMap::len)
+            .unwrap_or_default()
+    }
 
-        egg.find(self.node_to_egg[w1.0 .0]) == egg.find(self.node_to_egg[w2.0 .0])
+    /// Whether a theorem's conclusion matches the goal's expression.
+    pub fn goal_matches(&self, spec: &LevelSpec) -> bool {
+        !self
+            .egg
+            .search(&spec.conclusion_pattern(), self.goal().0 .0, &[])
+            .is_empty()
+    }
+
+    /// Match a theorem's conclusion against the goal, for applying it backward.
+    /// Fails unless it matches, and every variable of the conclusion is found.
+    pub fn match_goal(&self, spec: &LevelSpec) -> Option<HashMap<Var, Node>> {
+        if !self.goal_matches(spec) {
+            return None;
+        }
+        let chosen = self.match_theorem(
+            spec,
+            std::iter::once(spec.conclusion_pattern()),
+            self.goal().0,
+        );
+        spec.conclusion_vars()
+            .all(|v| chosen.contains_key(&v))
+            .then_some(chosen)
     }
 
     pub fn connect(&mut self, w1: Wire, w2: Wire, _why_valid: ValidityReason) {
@@ -147,11 +239,9 @@ impl Case {
             }
         }
 
-        Rc::make_mut(&mut self.connections).merge(w1.0, w2.0);
+        self.connections.merge(w1.0, w2.0);
 
-        self.egg
-            .borrow_mut()
-            .union(self.node_to_egg[w1.0 .0], self.node_to_egg[w2.0 .0]);
+        self.egg.union(w1.0 .0, w2.0 .0);
     }
 
     pub fn proven(&self, w: Wire) -> bool {
@@ -168,6 +258,17 @@ impl Case {
         self.nodes[node.0].deleted = true;
     }
 
+    /// Take a node back out of the trash.
+    pub fn restore(&mut self, node: Node) {
+        self.nodes[node.0].deleted = false;
+    }
+
+    pub fn deleted_nodes(&self) -> impl '_ + Iterator<Item = Node> {
+        (0..self.nodes.len())
+            .map(Node)
+            .filter(|n| self.nodes[n.0].deleted)
+    }
+
     pub fn position(&self, n: Node) -> [f64; 2] {
         self.nodes[n.0].position
     }
@@ -7,12 +7,13 @@ use crate::{
 use dodrio::{builder::*, bumpalo};
 use wasm_bindgen::JsCast;
 
-pub(super) fn render_node<'a>(
+pub(crate) fn render_node<'a>(
     cx: &mut dodrio::RenderContext<'a>,
     pos: [f64; 2],
     label: &'a str,
     events: Option<super::Node>,
     hoverable: bool,
+    dimmed: bool,
     ty: Type,
 ) -> dodrio::Node<'a> {
     let [x, y] = pos;
@@ -39,12 +40,25 @@ pub(super) fn render_node<'a>(
     ]);
     if let Some(node) = events {
         circle = circle.listeners(bumpalo::vec![in cx.bump;
-            Model::listener(cx.bump, "mousedown", move |e| {
-                let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "game");
-                crate::Msg::Level(level::Msg::MouseDown(x, y, level::DragObject::Node(node)))
+            // Only the primary button clicks and drags; the secondary one folds, through `contextmenu`.
+            Model::listener_raw(cx.bump, "mousedown", move |e, _, _, send_msg| {
+                let e = e.dyn_into::<web_sys::MouseEvent>().unwrap();
+                if e.button() == 0 {
+                    let (x, y) = to_svg_coords(e);
+                    send_msg
+                        .send_blocking(crate::Msg::Level(level::Msg::MouseDown(
+                            x,
+                            y,
+                            level::DragObject::Node(node),
+                        )))
+                        .unwrap();
+                }
+            }),
+            Model::listener(cx.bump, "contextmenu", move |_| {
+                crate::Msg::Level(level::Msg::FoldNode(node))
             }),
             Model::listener(cx.bump, "mouseup", move |e| {
-                let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "game");
+                let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
                 crate::Msg::Level(level::Msg::MouseUp(
                     x,
                     y,
@@ -55,6 +69,7 @@ pub(super) fn render_node<'a>(
     }
 
     g(cx.bump)
+        .attributes([attr("class", if dimmed { "dimmed" } else { "" })])
         .children([
             circle.finish(),
             text_(cx.bump)
@@ -66,7 +81,9 @@ pub(super) fn render_node<'a>(
 }
 
 /// `status` must be "" or " known" or " goal".
-pub(super) fn render_wire<'a>(
+/// With a `router`, the wire avoids the router's nodes.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_wire<'a>(
     cx: &mut dodrio::RenderContext<'a>,
     inputs: &[[f64; 2]],
     outputs: &[[f64; 2]],
@@ -74,6 +91,7 @@ pub(super) fn render_wire<'a>(
     status: &str,
     events: Option<super::Wire>,
     hoverable: bool,
+    mut router: Option<&mut bezier::Router>,
 ) -> [dodrio::Node<'a>; 2] {
     const R: f64 = 0.4;
     const WIRE_STIFFNESS: f64 = 0.5;
@@ -103,7 +121,7 @@ pub(super) fn render_wire<'a>(
     let output_avg = bezier::average(&outputs);
     let output_vector_avg = bezier::average(output_vectors);
 
-    let (mid, mid_vector) = bezier::split(
+    let (mut mid, mid_vector) = bezier::split(
         input_avg,
         input_vector,
         [
@@ -113,13 +131,21 @@ pub(super) fn render_wire<'a>(
         output_avg,
     );
 
+    if let Some(router) = &mut router {
+        mid = router.place_midpoint(mid, mid_vector);
+    }
+    let segment = |start, start_vector, end_vector, end, d: &mut _| match &router {
+        Some(router) => router.path(start, start_vector, end_vector, end, d),
+        None => bezier::path(start, start_vector, end_vector, end, d),
+    };
+
     let mut d = bumpalo::collections::String::new_in(cx.bump);
     for &(mut input) in inputs {
         input[1] += R;
-        bezier::path(input, input_vector, mid_vector, mid, &mut d);
+        segment(input, input_vector, mid_vector, mid, &mut d);
     }
     for (&output, &[x, y]) in outputs.iter().zip(output_vectors) {
-        bezier::path(
+        segment(
             mid,
             mid_vector,
             [x * WIRE_STIFFNESS, y * WIRE_STIFFNESS],
@@ -147,7 +173,7 @@ pub(super) fn render_wire<'a>(
 
     if let Some(wire) = events {
         let closure = move |e: web_sys::Event| {
-            let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "game");
+            let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
             crate::Msg::Level(level::Msg::MouseDown(x, y, level::DragObject::Wire(wire)))
         };
         out0 = out0
@@ -159,19 +185,55 @@ pub(super) fn render_wire<'a>(
     [out0.finish(), out1.finish()]
 }
 
+/// Which part of a case to draw. While a node is dragged, only it and its wires move,
+/// so the rest can be drawn once and kept until the drag ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Everything,
+    /// Everything but the dragged node and its wires.
+    Still,
+    /// Just the dragged node and its wires.
+    Moving,
+}
+
 impl super::Case {
+    /// If `highlight` is given, everything it doesn't point out is dimmed.
+    #[allow(clippy::too_many_arguments)]
     pub fn render<'a>(
         &self,
         cx: &mut dodrio::RenderContext<'a>,
         dragging: Option<super::Node>,
+        part: Part,
         events: bool,
+        highlight: Option<&level::tutorial::Step>,
+        wire_style: bezier::WireStyle,
         node_hoverable: impl Fn(super::Node) -> bool,
         wire_hoverable: impl Fn(super::Wire) -> bool,
     ) -> [dodrio::Node<'a>; 2] {
+        let node_dimmed = |node: super::Node| {
+            highlight.is_some_and(|step| {
+                !step
+                    .nodes
+                    .iter()
+                    .any(|&ix| super::Node::from_spec(ix) == node)
+            })
+        };
+        let wire_dimmed = |wire: super::Wire| {
+            highlight.is_some_and(|step| {
+                !step
+                    .wires
+                    .iter()
+                    .any(|&ix| self.wire_eq(self.node_output(super::Node::from_spec(ix)), wire))
+            })
+        };
+
         [
             // Wires
             {
                 let mut builder = g(cx.bump);
+                let mut router = (wire_style == bezier::WireStyle::Routed).then(|| {
+                    bezier::Router::new(self.nodes().map(|node| self.position(node)).collect())
+                });
 
                 let (wires_dragged, wires_static) =
                     self.wires().partition::<Vec<_>, _>(|(w, outputs)| {
@@ -179,9 +241,16 @@ impl super::Case {
                             .chain(outputs.iter().map(|(n, _)| *n))
                             .any(|n| Some(n) == dragging)
                     });
-                for (wire, outputs) in wires_static.into_iter().chain(wires_dragged) {
+                let wires = match part {
+                    Part::Everything => [wires_static, wires_dragged].concat(),
+                    Part::Still => wires_static,
+                    Part::Moving => wires_dragged,
+                };
+                for (wire, outputs) in wires {
                     use bumpalo::collections::Vec;
 
+                    let mut group = g(cx.bump)
+                        .attributes([attr("class", if wire_dimmed(wire) { "dimmed" } else { "" })]);
                     for svg_node in render_wire(
                         cx,
                         &Vec::from_iter_in(
@@ -216,9 +285,11 @@ impl super::Case {
                         },
                         (events && dragging.is_none()).then_some(wire),
                         dragging.is_none() && wire_hoverable(wire),
+                        router.as_mut(),
                     ) {
-                        builder = builder.child(svg_node);
+                        group = group.child(svg_node);
                     }
+                    builder = builder.child(group.finish());
                 }
                 builder.finish()
             },
@@ -226,7 +297,7 @@ impl super::Case {
             {
                 let mut builder = g(cx.bump);
                 for node in self.nodes() {
-                    if dragging != Some(node) {
+                    if dragging != Some(node) && part != Part::Moving {
                         builder = builder.child(render_node(
                             cx,
                             self.position(node),
@@ -237,11 +308,12 @@ impl super::Case {
                             .into_bump_str(),
                             events.then_some(node),
                             dragging.is_none() && node_hoverable(node),
+                            node_dimmed(node),
                             self.ty(self.node_output(node)),
                         ));
                     }
                 }
-                if let Some(node) = dragging {
+                if let Some(node) = dragging.filter(|_| part != Part::Still) {
                     builder = builder.child(render_node(
                         cx,
                         self.position(node),
@@ -252,6 +324,7 @@ impl super::Case {
                         .into_bump_str(),
                         None,
                         false,
+                        false,
                         self.ty(self.node_output(node)),
                     ));
                 }
@@ -1,10 +1,45 @@
 mod render;
+mod tautology;
+
+pub use tautology::Propositional;
+
+use std::rc::Rc;
 
 use super::{
-    super::expression::{Expression, Var},
+    super::expression::{pattern, Definitions, Expression, Rewrites, Type, Var},
     Case, ValidityReason,
 };
 
+/// The FNV-1a hash, over an encoding of our own, for hashes that are saved.
+/// Unlike `std`'s hasher and `Hash`, it stays the same from one build of the game to the next.
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) fn number(&mut self, n: u64) {
+        self.bytes(&n.to_le_bytes());
+    }
+
+    /// The length comes first, so that one string can't run into the next.
+    pub(crate) fn text(&mut self, text: &str) {
+        self.number(text.len() as u64);
+        self.bytes(text.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct LevelSpec {
     /// Invariant: `nodes[n].inputs()[k] < n`.
@@ -13,6 +48,10 @@ pub struct LevelSpec {
     hypotheses: Vec<usize>,
     /// Invariant: `conclusion < nodes.len()`
     conclusion: usize,
+    /// The level pack's defined function symbols, which cases of this level can unfold.
+    definitions: Rc<Definitions>,
+    /// The equations that cases of this level apply by themselves.
+    rewrites: Rewrites,
 }
 
 impl LevelSpec {
@@ -21,11 +60,11 @@ impl LevelSpec {
         nodes: Vec<(Expression<usize>, [f64; 2])>,
         hypotheses: Vec<usize>,
         conclusion: usize,
+        definitions: Rc<Definitions>,
+        rewrites: Rewrites,
     ) -> anyhow::Result<Self> {
+        // Indices are checked before they're used, since a level pack can be anything.
         for (n, (expression, _)) in nodes.iter().enumerate() {
-            if !expression.tycheck(|node| nodes[*node].0.ty()) {
-                anyhow::bail!("Node {} fails typechecking.", n)
-            }
             for ix in expression.inputs() {
                 match ix.cmp(&n) {
                     std::cmp::Ordering::Less => {}
@@ -35,20 +74,20 @@ impl LevelSpec {
                     }
                 }
             }
+            if !expression.tycheck(|node| nodes[*node].0.ty()) {
+                anyhow::bail!("Node {} fails typechecking.", n)
+            }
         }
 
         for &ix in &hypotheses {
-            if nodes[ix].0.ty() != super::Type::TruthValue {
-                anyhow::bail!("Hypothesis {} is not a truth value.", ix);
-            }
             if ix >= nodes.len() {
                 anyhow::bail!("Hypothesis index too large. ({} >= {})", ix, nodes.len())
             }
+            if nodes[ix].0.ty() != super::Type::TruthValue {
+                anyhow::bail!("Hypothesis {} is not a truth value.", ix);
+            }
         }
 
-        if nodes[conclusion].0.ty() != super::Type::TruthValue {
-            anyhow::bail!("Conclusion is not a truth value.");
-        }
         if conclusion >= nodes.len() {
             anyhow::bail!(
                 "Conclusion index too large. ({} >= {})",
@@ -56,16 +95,43 @@ impl LevelSpec {
                 nodes.len()
             )
         }
+        if nodes[conclusion].0.ty() != super::Type::TruthValue {
+            anyhow::bail!("Conclusion is not a truth value.");
+        }
 
         Ok(Self {
             nodes,
             hypotheses,
             conclusion,
+            definitions,
+            rewrites,
         })
     }
 
+    /// The same statement, with `rewrites` active instead.
+    pub fn with_rewrites(self, rewrites: Rewrites) -> Self {
+        Self { rewrites, ..self }
+    }
+
+    /// The levels whose theorems the level's rewrites are.
+    pub fn rewrite_theorems(&self) -> &[usize] {
+        self.rewrites.theorems()
+    }
+
+    pub fn nodes(&self) -> &[(Expression<usize>, [f64; 2])] {
+        &self.nodes
+    }
+
+    pub fn hypotheses(&self) -> &[usize] {
+        &self.hypotheses
+    }
+
+    pub fn conclusion(&self) -> usize {
+        self.conclusion
+    }
+
     pub fn to_case(&self, offset: [f64; 2]) -> Case {
-        let mut case = Case::new();
+        let mut case = Case::new(self.definitions.clone(), self.rewrites.clone());
         let mut wires = Vec::with_capacity(self.nodes.len());
 
         for (expression, position) in &self.nodes {
@@ -85,6 +151,104 @@ impl LevelSpec {
         case
     }
 
+    /// The statement of the theorem, written as `hypotheses ⊢ conclusion`.
+    pub fn statement(&self) -> String {
+        let conclusion = self.formula(self.conclusion);
+        if self.hypotheses.is_empty() {
+            conclusion
+        } else {
+            let hypotheses = self
+                .hypotheses
+                .iter()
+                .map(|&h| self.formula(h))
+                .collect::<Vec<_>>();
+            format!("{} ⊢ {}", hypotheses.join(", "), conclusion)
+        }
+    }
+
+    /// A hash of the theorem, for noticing when a level has changed since it was proven.
+    /// It covers each node's symbol, type and inputs, then the hypotheses and the conclusion,
+    /// so moving the nodes around doesn't change it.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = Fnv::new();
+        for (expression, _) in &self.nodes {
+            let (tag, name, ty) = match expression {
+                Expression::And(_) => (0, "", Type::TruthValue),
+                Expression::Or(_) => (1, "", Type::TruthValue),
+                Expression::Implies(_) => (2, "", Type::TruthValue),
+                Expression::Equal(_) => (3, "", Type::TruthValue),
+                Expression::Variable(Var(name, ty)) => (4, name.as_str(), *ty),
+                Expression::Function(f, ty, _) => (5, f.as_str(), *ty),
+            };
+            hash.number(tag);
+            hash.text(name);
+            hash.number(match ty {
+                Type::TruthValue => 0,
+                Type::RealNumber => 1,
+            });
+            hash.number(expression.inputs().len() as u64);
+            for &input in expression.inputs() {
+                hash.number(input as u64);
+            }
+        }
+        hash.number(self.hypotheses.len() as u64);
+        for &hypothesis in &self.hypotheses {
+            hash.number(hypothesis as u64);
+        }
+        hash.number(self.conclusion as u64);
+        hash.finish()
+    }
+
+    fn formula(&self, node: usize) -> String {
+        self.nodes[node].0.formula(|&input| {
+            if self.nodes[input].0.inputs().is_empty() {
+                self.formula(input)
+            } else {
+                format!("({})", self.formula(input))
+            }
+        })
+    }
+
+    /// Patterns for the conclusion, then each hypothesis, to match against a case's expressions.
+    pub fn patterns(&self) -> impl '_ + Iterator<Item = egg::Pattern<Expression<egg::Id>>> {
+        std::iter::once(self.conclusion)
+            .chain(self.hypotheses.iter().copied())
+            .map(|root| pattern(root, |ix| self.nodes[ix].0.clone()))
+    }
+
+    pub fn conclusion_pattern(&self) -> egg::Pattern<Expression<egg::Id>> {
+        pattern(self.conclusion, |ix| self.nodes[ix].0.clone())
+    }
+
+    /// The variables the conclusion mentions.
+    pub fn conclusion_vars(&self) -> impl '_ + Iterator<Item = Var> {
+        let used = self.used_by([self.conclusion]);
+        self.nodes
+            .iter()
+            .zip(used)
+            .filter_map(|((e, _), used)| match e {
+                Expression::Variable(v) if used => Some(v.clone()),
+                _ => None,
+            })
+    }
+
+    /// Which nodes the given nodes are built from, including themselves.
+    fn used_by(&self, roots: impl IntoIterator<Item = usize>) -> Vec<bool> {
+        let mut used = vec![false; self.nodes.len()];
+        for root in roots {
+            used[root] = true;
+        }
+        // Inputs come before the nodes using them.
+        for n in (0..self.nodes.len()).rev() {
+            if used[n] {
+                for &input in self.nodes[n].0.inputs() {
+                    used[input] = true;
+                }
+            }
+        }
+        used
+    }
+
     pub fn vars(&self) -> impl '_ + Iterator<Item = Var> {
         self.nodes.iter().filter_map(|(e, _)| {
             if let Expression::Variable(v) = e {
@@ -95,28 +259,42 @@ impl LevelSpec {
         })
     }
 
+    /// Apply the theorem, proving its conclusion in one case, and each hypothesis in one more.
+    /// Applied backward, the conclusion has been matched to the goal, by `Case::match_goal`,
+    /// so only the hypotheses' nodes are created, and it's the goal that gets proven.
     pub fn add_to_case_tree(
         self,
         case_tree: &mut super::super::case_tree::CaseTree,
+        theorem: usize,
         var: impl Fn(&Var) -> super::Node,
         offset: [f64; 2],
+        backward: bool,
     ) {
-        let mut wires = Vec::with_capacity(self.nodes.len());
+        let mut wires: Vec<Option<super::Wire>> = Vec::with_capacity(self.nodes.len());
+        let needed = if backward {
+            self.used_by(self.hypotheses.iter().copied())
+        } else {
+            vec![true; self.nodes.len()]
+        };
 
         let mut case = case_tree.case(case_tree.current).0.clone();
 
         // Create Nodes
-        for (expression, position) in self.nodes {
+        for ((expression, position), needed) in self.nodes.into_iter().zip(needed) {
             let node = if let Expression::Variable(v) = &expression {
                 var(v)
-            } else {
+            } else if needed {
                 case.make_node(
-                    expression.map(|ix| wires[ix]),
+                    expression.map(|ix: usize| wires[ix].unwrap()),
                     [position[0] + offset[0], position[1] + offset[1]],
                 )
+            } else {
+                wires.push(None);
+                continue;
             };
-            wires.push(case.node_output(node));
+            wires.push(Some(case.node_output(node)));
         }
+        let wires = |ix: usize| wires[ix].unwrap();
 
         // Hypotheses
         let mut subcases = self
@@ -124,19 +302,32 @@ impl LevelSpec {
             .into_iter()
             .map(|h| {
                 let mut case = case.clone();
-                case.set_goal(wires[h]);
+                case.set_goal(wires(h));
                 case
             })
             .collect::<Vec<_>>();
 
         // Conclusion
-        case.set_proven(
-            wires[self.conclusion],
-            ValidityReason::new("Application of a previously proven theorem."),
-        );
+        if backward {
+            let goal = case.goal();
+            case.set_proven(
+                goal,
+                ValidityReason::new(
+                    r"
+Application of a previously proven theorem.
+`Case::match_goal` found the goal to be equivalent to the conclusion,
+with every variable of the conclusion already chosen.",
+                ),
+            );
+        } else {
+            case.set_proven(
+                wires(self.conclusion),
+                ValidityReason::new("Application of a previously proven theorem."),
+            );
+        }
         subcases.push(case);
 
         // Case Split
-        case_tree.case_split(subcases);
+        case_tree.apply_theorem(theorem, subcases);
     }
 }
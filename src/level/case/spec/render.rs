@@ -87,6 +87,7 @@ impl LevelSpec {
                         },
                         None,
                         false,
+                        None,
                     ) {
                         builder = builder.child(svg_node);
                     }
@@ -109,6 +110,7 @@ impl LevelSpec {
                         },
                         None,
                         false,
+                        false,
                         expression.ty(),
                     ));
                 }
@@ -0,0 +1,243 @@
+//! Checking whether a level's conclusion follows from its hypotheses by propositional logic alone.
+//!
+//! Equations and function applications are treated as opaque propositions, so a level that isn't
+//! a tautology may still be provable, but only with theorems or by reasoning about equality.
+
+use std::collections::HashMap;
+
+use super::LevelSpec;
+use crate::level::expression::{Expression, Type};
+
+/// The truth table has `2^atoms` rows, so past this many atoms the check gives up.
+const MAX_ATOMS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Propositional {
+    Tautology,
+    /// A truth value for each atom, by its formula, which makes every hypothesis true and the conclusion false.
+    Counterexample(Vec<(String, bool)>),
+    /// The statement has too many atoms to check.
+    TooLarge(usize),
+}
+
+impl Propositional {
+    /// The counterexample, if there is one, written like `p = ⊤, q = ⊥`.
+    pub fn counterexample(&self) -> Option<String> {
+        match self {
+            Propositional::Counterexample(assignment) => Some(
+                assignment
+                    .iter()
+                    .map(|(atom, value)| format!("{atom} = {}", if *value { "⊤" } else { "⊥" }))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl LevelSpec {
+    /// Check the statement with a truth table.
+    /// Nodes that are built the same way are the same atom, and an equation between them is true.
+    pub fn propositional(&self) -> Propositional {
+        self.truth_table(true)
+    }
+
+    /// Check the statement with every equation and function application taken to be true,
+    /// so that only truth-valued variables vary. A statement that fails even so
+    /// can't be proven by reasoning about equality, whatever its equations say.
+    pub fn propositional_with_atoms_true(&self) -> Propositional {
+        self.truth_table(false)
+    }
+
+    /// Whether any node of the statement is written `formula`.
+    pub fn mentions(&self, formula: &str) -> bool {
+        let used = self.used_by(self.hypotheses.iter().copied().chain([self.conclusion]));
+        (0..self.nodes.len()).any(|n| used[n] && self.formula(n) == formula)
+    }
+
+    /// `opaque` is whether equations and function applications are atoms, rather than true.
+    fn truth_table(&self, opaque: bool) -> Propositional {
+        let used = self.used_by(self.hypotheses.iter().copied().chain([self.conclusion]));
+
+        // Number each node by the first node built the same way.
+        let mut first = HashMap::new();
+        let mut canonical = Vec::with_capacity(self.nodes.len());
+        for (n, (expression, _)) in self.nodes.iter().enumerate() {
+            let key = expression.clone().map(|input| canonical[input]);
+            canonical.push(*first.entry(key).or_insert(n));
+        }
+
+        let mut atoms = HashMap::new();
+        let mut atom = vec![None; self.nodes.len()];
+        for (n, (expression, _)) in self.nodes.iter().enumerate() {
+            if !used[n] || expression.ty() != Type::TruthValue {
+                continue;
+            }
+            match expression {
+                Expression::And(_) | Expression::Or(_) | Expression::Implies(_) => {}
+                Expression::Equal([a, b]) if canonical[*a] == canonical[*b] => {}
+                Expression::Equal(_) | Expression::Function(..) if !opaque => {}
+                _ => {
+                    let next = atoms.len();
+                    atom[n] = Some(*atoms.entry(canonical[n]).or_insert(next));
+                }
+            }
+        }
+        if atoms.len() > MAX_ATOMS {
+            return Propositional::TooLarge(atoms.len());
+        }
+
+        let mut values = vec![true; self.nodes.len()];
+        for row in 0u32..1 << atoms.len() {
+            for (n, (expression, _)) in self.nodes.iter().enumerate() {
+                if !used[n] {
+                    continue;
+                }
+                values[n] = match (atom[n], expression) {
+                    (Some(atom), _) => row >> atom & 1 == 1,
+                    (None, Expression::And(inputs)) => inputs.iter().all(|&x| values[x]),
+                    (None, Expression::Or(inputs)) => inputs.iter().any(|&x| values[x]),
+                    (None, Expression::Implies([a, b])) => !values[*a] || values[*b],
+                    // An equation between the same two things, an atom taken to be true, or a number.
+                    (None, _) => true,
+                };
+            }
+            if self.hypotheses.iter().all(|&h| values[h]) && !values[self.conclusion] {
+                let mut assignment = atoms
+                    .iter()
+                    .map(|(&node, &atom)| (self.formula(node), row >> atom & 1 == 1))
+                    .collect::<Vec<_>>();
+                assignment.sort();
+                return Propositional::Counterexample(assignment);
+            }
+        }
+        Propositional::Tautology
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::level::expression::{Definitions, Rewrites, Var};
+
+    fn var(name: &str, ty: Type) -> Expression<usize> {
+        Expression::Variable(Var(name.to_owned(), ty))
+    }
+
+    fn p(name: &str) -> Expression<usize> {
+        var(name, Type::TruthValue)
+    }
+
+    fn x(name: &str) -> Expression<usize> {
+        var(name, Type::RealNumber)
+    }
+
+    fn spec(nodes: Vec<Expression<usize>>, hypotheses: &[usize], conclusion: usize) -> LevelSpec {
+        LevelSpec::new(
+            nodes.into_iter().map(|e| (e, [0., 0.])).collect(),
+            hypotheses.to_vec(),
+            conclusion,
+            Rc::new(Definitions::new()),
+            Rewrites::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn tautology() {
+        let nodes = vec![
+            p("p"),
+            p("q"),
+            Expression::And([0, 1].into()),
+            Expression::And([1, 0].into()),
+        ];
+        assert_eq!(
+            spec(nodes, &[2], 3).propositional(),
+            Propositional::Tautology
+        );
+    }
+
+    #[test]
+    fn counterexample() {
+        let nodes = vec![p("p"), p("q"), Expression::Or([0, 1].into())];
+        let result = spec(nodes, &[2], 0).propositional();
+        assert_eq!(
+            result,
+            Propositional::Counterexample(vec![("p".to_owned(), false), ("q".to_owned(), true)])
+        );
+        assert_eq!(result.counterexample().as_deref(), Some("p = ⊥, q = ⊤"));
+    }
+
+    #[test]
+    fn nodes_built_the_same_way_are_the_same_atom() {
+        let nodes = vec![p("p"), p("p"), Expression::Implies([0, 1])];
+        assert_eq!(
+            spec(nodes, &[], 2).propositional(),
+            Propositional::Tautology
+        );
+
+        let nodes = vec![x("a"), x("a"), Expression::Equal([0, 1])];
+        assert_eq!(
+            spec(nodes, &[], 2).propositional(),
+            Propositional::Tautology
+        );
+    }
+
+    #[test]
+    fn equations_are_opaque_unless_taken_to_be_true() {
+        let nodes = vec![
+            x("a"),
+            x("b"),
+            Expression::Equal([0, 1]),
+            Expression::Equal([1, 0]),
+        ];
+        let spec = spec(nodes, &[2], 3);
+        assert!(matches!(
+            spec.propositional(),
+            Propositional::Counterexample(_)
+        ));
+        assert_eq!(
+            spec.propositional_with_atoms_true(),
+            Propositional::Tautology
+        );
+    }
+
+    #[test]
+    fn truth_valued_variables_vary_even_with_atoms_true() {
+        let nodes = vec![
+            p("p"),
+            x("a"),
+            x("b"),
+            Expression::Equal([1, 2]),
+            Expression::And([0, 3].into()),
+        ];
+        assert_eq!(
+            spec(nodes, &[], 4).propositional_with_atoms_true(),
+            Propositional::Counterexample(vec![("p".to_owned(), false)])
+        );
+    }
+
+    #[test]
+    fn too_large() {
+        let mut nodes = (0..=MAX_ATOMS)
+            .map(|n| p(&format!("p{n}")))
+            .collect::<Vec<_>>();
+        nodes.push(Expression::Or((0..=MAX_ATOMS).collect()));
+        assert_eq!(
+            spec(nodes, &[], MAX_ATOMS + 1).propositional(),
+            Propositional::TooLarge(MAX_ATOMS + 1)
+        );
+    }
+
+    #[test]
+    fn mentions_only_what_the_statement_uses() {
+        let nodes = vec![p("p"), p("q"), p("r"), Expression::Or([0, 1].into())];
+        let spec = spec(nodes, &[3], 0);
+        assert!(spec.mentions("q"));
+        assert!(spec.mentions("p ∨ q"));
+        assert!(!spec.mentions("r"));
+    }
+}
@@ -1,91 +1,184 @@
-use std::{cell::RefCell, collections::HashMap, hash::Hash};
+use im_rc::Vector;
+use std::cell::RefCell;
 
+/// Anything identified by a dense index, like the nodes of a case.
+pub trait Key: Copy + Eq {
+    fn index(self) -> usize;
+    fn from_index(index: usize) -> Self;
+}
+
+/// A union-find over densely indexed keys.
+/// Its vectors are persistent, so clones share structure,
+/// and a change copies only the few chunks it touches.
+///
+/// Keys that have never been mentioned are in classes of their own.
 #[derive(Debug, Clone)]
 pub struct UnionFind<T> {
-    tree: RefCell<HashMap<T, Node<T>>>,
-    cycles: HashMap<T, T>,
+    tree: RefCell<Vector<Node>>,
+    /// Each class is a cycle, so that it can be iterated over.
+    cycles: Vector<usize>,
+    _phantom: std::marker::PhantomData<T>,
 }
 
 #[derive(Debug, Clone, Copy)]
-enum Node<T> {
+enum Node {
     Root { rank: u8 },
-    Child { parent: T },
+    Child { parent: usize },
 }
 
-impl<T: Copy + Eq + Hash> UnionFind<T> {
+impl<T: Key> UnionFind<T> {
     pub fn new() -> Self {
         Self {
-            tree: RefCell::new(HashMap::new()),
-            cycles: HashMap::new(),
+            tree: RefCell::new(Vector::new()),
+            cycles: Vector::new(),
+            _phantom: std::marker::PhantomData,
         }
     }
 
-    fn find(&self, node: T) -> (T, u8) {
-        let x = self.tree.borrow().get(&node).copied();
+    fn find(&self, node: usize) -> (usize, u8) {
+        let x = self.tree.borrow().get(node).copied();
         match x {
             Some(Node::Root { rank }) => (node, rank),
             Some(Node::Child { parent }) => {
                 let (root, rank) = self.find(parent);
-                self.tree
-                    .borrow_mut()
-                    .insert(node, Node::Child { parent: root });
+                if root != parent {
+                    // Path compression.
+                    self.tree.borrow_mut()[node] = Node::Child { parent: root };
+                }
                 (root, rank)
             }
-            None => {
-                self.tree.borrow_mut().insert(node, Node::Root { rank: 0 });
-                (node, 0)
-            }
+            None => (node, 0),
+        }
+    }
+
+    /// Make sure `node` has an entry in both vectors.
+    fn extend_to(&mut self, node: usize) {
+        let tree = self.tree.get_mut();
+        while tree.len() <= node {
+            tree.push_back(Node::Root { rank: 0 });
+        }
+        while self.cycles.len() <= node {
+            self.cycles.push_back(self.cycles.len());
         }
     }
 
     pub fn canonical(&self, node: T) -> T {
-        self.find(node).0
+        T::from_index(self.find(node.index()).0)
     }
 
     pub fn eq(&self, n1: T, n2: T) -> bool {
-        self.canonical(n1) == self.canonical(n2)
+        self.find(n1.index()).0 == self.find(n2.index()).0
     }
 
     pub fn merge(&mut self, n1: T, n2: T) {
-        let (r1, rank1) = self.find(n1);
-        let (r2, rank2) = self.find(n2);
+        let (r1, rank1) = self.find(n1.index());
+        let (r2, rank2) = self.find(n2.index());
 
         if r1 == r2 {
             return;
         }
+        self.extend_to(r1.max(r2));
 
         // Cycle update
         {
-            let s1 = *self.cycles.get(&r1).unwrap_or(&r1);
-            let s2 = self.cycles.insert(r2, s1).unwrap_or(r2);
-            self.cycles.insert(r1, s2);
+            let s1 = self.cycles[r1];
+            let s2 = std::mem::replace(&mut self.cycles[r2], s1);
+            self.cycles[r1] = s2;
         }
 
-        let mut tree = self.tree.borrow_mut();
+        let tree = self.tree.get_mut();
         if rank1 < rank2 {
-            tree.insert(r1, Node::Child { parent: r2 });
-            tree.insert(r2, Node::Root { rank: rank2 });
+            tree[r1] = Node::Child { parent: r2 };
         } else {
-            tree.insert(r2, Node::Child { parent: r1 });
-            tree.insert(
-                r1,
-                Node::Root {
-                    rank: if rank1 == rank2 { rank1 + 1 } else { rank1 },
-                },
-            );
+            tree[r2] = Node::Child { parent: r1 };
+            if rank1 == rank2 {
+                tree[r1] = Node::Root { rank: rank1 + 1 };
+            }
         }
     }
 
     /// Iterator over all the nodes in the same equivalence class as `e`.
     pub fn iter_class(&self, e: T) -> impl Iterator<Item = T> + '_ {
-        let mut node = e;
-        std::iter::once(node).chain(std::iter::from_fn(move || {
-            node = *self.cycles.get(&node).unwrap_or(&node);
-            if node == e {
+        let start = e.index();
+        let mut node = start;
+        std::iter::once(e).chain(std::iter::from_fn(move || {
+            node = self.cycles.get(node).copied().unwrap_or(node);
+            if node == start {
                 None
             } else {
-                Some(node)
+                Some(T::from_index(node))
             }
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    struct N(usize);
+
+    impl Key for N {
+        fn index(self) -> usize {
+            self.0
+        }
+
+        fn from_index(index: usize) -> Self {
+            N(index)
+        }
+    }
+
+    fn class(uf: &UnionFind<N>, n: usize) -> Vec<usize> {
+        let mut class = uf.iter_class(N(n)).map(|N(n)| n).collect::<Vec<_>>();
+        class.sort();
+        class
+    }
+
+    #[test]
+    fn keys_start_alone() {
+        let uf = UnionFind::new();
+        assert_eq!(uf.canonical(N(7)), N(7));
+        assert!(!uf.eq(N(1), N(2)));
+        assert_eq!(class(&uf, 3), [3]);
+    }
+
+    #[test]
+    fn merging_joins_classes() {
+        let mut uf = UnionFind::new();
+        uf.merge(N(0), N(1));
+        uf.merge(N(2), N(3));
+        uf.merge(N(1), N(3));
+        uf.merge(N(0), N(2));
+        assert!(uf.eq(N(0), N(3)));
+        assert_eq!(uf.canonical(N(2)), uf.canonical(N(1)));
+        for n in 0..4 {
+            assert_eq!(class(&uf, n), [0, 1, 2, 3]);
+        }
+        assert_eq!(class(&uf, 4), [4]);
+    }
+
+    #[test]
+    fn keys_past_the_end() {
+        let mut uf = UnionFind::new();
+        uf.merge(N(100), N(0));
+        assert!(uf.eq(N(0), N(100)));
+        assert_eq!(class(&uf, 100), [0, 100]);
+        assert_eq!(class(&uf, 50), [50]);
+        assert_eq!(class(&uf, 200), [200]);
+    }
+
+    #[test]
+    fn clones_are_independent() {
+        let mut uf = UnionFind::new();
+        uf.merge(N(0), N(1));
+        let mut copy = uf.clone();
+        copy.merge(N(1), N(2));
+        assert_eq!(class(&copy, 0), [0, 1, 2]);
+        assert_eq!(class(&uf, 0), [0, 1]);
+        assert!(!uf.eq(N(0), N(2)));
+
+        uf.merge(N(2), N(3));
+        assert_eq!(class(&copy, 3), [3]);
+    }
+}
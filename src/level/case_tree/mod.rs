@@ -1,23 +1,33 @@
 mod render;
 
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::BTreeSet,
+    ops::{Deref, DerefMut},
+};
 
 use super::case::*;
 
 use smallvec::SmallVec;
 
+#[derive(Clone)]
 pub struct CaseTree {
     nodes: Vec<CaseNode>,
     pub current: CaseId,
     free_list: SmallVec<[usize; 2]>,
 }
 
+#[derive(Clone)]
 struct CaseNode {
     case: Case,
     complete: bool,
     parent: usize,
     /// `None` for leaf nodes; `Some` for branches.
     children: Option<SmallVec<[usize; 2]>>,
+    /// If this branch was created by applying a theorem, the level that theorem comes from.
+    theorem: Option<usize>,
+    /// The levels stating the definitions unfolded or folded in this case.
+    /// They stay when the case is reverted to, like the nodes the unfolding made.
+    definitions: BTreeSet<usize>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -30,6 +40,8 @@ impl CaseNode {
             case,
             parent,
             children: None,
+            theorem: None,
+            definitions: BTreeSet::new(),
         }
     }
 }
@@ -113,6 +125,34 @@ impl CaseTree {
         }
     }
 
+    pub fn apply_theorem(&mut self, theorem: usize, subcases: impl IntoIterator<Item = Case>) {
+        self.nodes[self.current.0].theorem = Some(theorem);
+        self.case_split(subcases)
+    }
+
+    /// Record that the current case unfolded or folded a definition stated by `level`.
+    pub(super) fn record_definition(&mut self, level: Option<usize>) {
+        self.nodes[self.current.0].definitions.extend(level);
+    }
+
+    /// The theorems applied anywhere in the tree, along with those behind the level's rewrites,
+    /// and those stating the definitions that were unfolded or folded.
+    pub fn theorems_used(&self) -> BTreeSet<usize> {
+        let mut out = self.nodes[0]
+            .case
+            .rewrite_theorems()
+            .iter()
+            .copied()
+            .collect::<BTreeSet<_>>();
+        let mut work = vec![0];
+        while let Some(node) = work.pop() {
+            out.extend(self.nodes[node].theorem);
+            out.extend(&self.nodes[node].definitions);
+            work.extend(self.nodes[node].children.iter().flatten());
+        }
+        out
+    }
+
     pub fn set_node_position(&mut self, node: Node, position: [f64; 2]) {
         self.nodes[self.current.0].case.set_position(node, position)
     }
@@ -122,6 +162,7 @@ impl CaseTree {
     }
 
     pub fn revert_to(&mut self, case: CaseId) {
+        self.nodes[case.0].theorem = None;
         let mut work = self.nodes[case.0].children.take().unwrap_or_default();
         while let Some(node) = work.pop() {
             self.free_list.push(node);
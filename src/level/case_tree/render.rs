@@ -194,15 +194,14 @@ impl CaseTree {
         }
 
         svg.attributes([
-            attr("id", "class-tree"),
             attr(
                 "class",
                 if axiom {
-                    "background axiom"
+                    "class-tree background axiom"
                 } else if self.all_complete() {
-                    "background complete"
+                    "class-tree background complete"
                 } else {
-                    "background"
+                    "class-tree background"
                 },
             ),
             attr("preserveAspectRatio", "xMidYMax meet"),
@@ -0,0 +1,289 @@
+//! Exact arithmetic on numeral literals, for levels that let the e-graph do sums by itself.
+//!
+//! A numeral is any function symbol of no inputs that reads as a rational number,
+//! like `12`, `-1`, `2.5`, `3/4` or `½`. With evaluation on, every real expression built from numerals
+//! by the basic operations is equivalent to the numeral for its value, so `2 + 2` connects to `4`.
+
+use super::{Expression, Type};
+
+/// A rational number in lowest terms, with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i64,
+    den: i64,
+}
+
+const VULGAR_FRACTIONS: &[(char, i64, i64)] = &[
+    ('½', 1, 2),
+    ('⅓', 1, 3),
+    ('⅔', 2, 3),
+    ('¼', 1, 4),
+    ('¾', 3, 4),
+    ('⅕', 1, 5),
+    ('⅖', 2, 5),
+    ('⅗', 3, 5),
+    ('⅘', 4, 5),
+    ('⅙', 1, 6),
+    ('⅚', 5, 6),
+    ('⅐', 1, 7),
+    ('⅛', 1, 8),
+    ('⅜', 3, 8),
+    ('⅝', 5, 8),
+    ('⅞', 7, 8),
+    ('⅑', 1, 9),
+    ('⅒', 1, 10),
+];
+
+impl Rational {
+    /// `None` if the denominator is zero, or the result doesn't fit.
+    fn new(num: i128, den: i128) -> Option<Self> {
+        if den == 0 {
+            return None;
+        }
+        let g = gcd(num.unsigned_abs(), den.unsigned_abs()) as i128;
+        let sign = den.signum();
+        Some(Self {
+            num: (sign * num / g).try_into().ok()?,
+            den: (sign * den / g).try_into().ok()?,
+        })
+    }
+
+    fn integer(n: i64) -> Self {
+        Self { num: n, den: 1 }
+    }
+
+    /// Read a numeral. Returns `None` for anything else, including the empty string and `-`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+        let digits = |s: &str| -> Option<i128> {
+            if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            s.parse().ok()
+        };
+
+        let value = if let Some(&(_, num, den)) = VULGAR_FRACTIONS
+            .iter()
+            .find(|&&(c, _, _)| s.chars().eq([c]))
+        {
+            Self::new(num.into(), den.into())?
+        } else if let Some((num, den)) = s.split_once('/') {
+            Self::new(digits(num)?, digits(den)?)?
+        } else if let Some((int, frac)) = s.split_once('.') {
+            let scale = 10i128.checked_pow(frac.len().try_into().ok()?)?;
+            Self::new(digits(int)?.checked_mul(scale)? + digits(frac)?, scale)?
+        } else {
+            Self::new(digits(s)?, 1)?
+        };
+
+        if negative {
+            value.neg()
+        } else {
+            Some(value)
+        }
+    }
+
+    fn neg(self) -> Option<Self> {
+        Self::new(-i128::from(self.num), self.den.into())
+    }
+
+    fn add(self, other: Self) -> Option<Self> {
+        let [a, b, c, d] = [self.num, self.den, other.num, other.den].map(i128::from);
+        Self::new(
+            a.checked_mul(d)?.checked_add(c.checked_mul(b)?)?,
+            b.checked_mul(d)?,
+        )
+    }
+
+    fn mul(self, other: Self) -> Option<Self> {
+        let [a, b, c, d] = [self.num, self.den, other.num, other.den].map(i128::from);
+        Self::new(a.checked_mul(c)?, b.checked_mul(d)?)
+    }
+
+    fn recip(self) -> Option<Self> {
+        Self::new(self.den.into(), self.num.into())
+    }
+
+    /// Only small integer exponents are evaluated. `0^0` is left undefined,
+    /// as is a negative power of zero.
+    fn pow(self, exponent: Self) -> Option<Self> {
+        if exponent.den != 1 || exponent.num.unsigned_abs() > 64 {
+            return None;
+        }
+        if self.num == 0 && exponent.num <= 0 {
+            return None;
+        }
+        let base = if exponent.num < 0 {
+            self.recip()?
+        } else {
+            self
+        };
+        (0..exponent.num.unsigned_abs()).try_fold(Self::integer(1), |result, _| result.mul(base))
+    }
+
+    /// The value of a function symbol applied to numbers, if it's a numeral or a basic operation,
+    /// and the result is defined and fits.
+    pub fn apply(f: &str, inputs: &[Self]) -> Option<Self> {
+        match (f, inputs) {
+            (f, []) => Self::parse(f),
+            ("+", inputs) => inputs
+                .iter()
+                .try_fold(Self::integer(0), |sum, &x| sum.add(x)),
+            ("×", inputs) => inputs
+                .iter()
+                .try_fold(Self::integer(1), |product, &x| product.mul(x)),
+            ("-", [a]) => a.neg(),
+            ("−", [a, b]) => a.add(b.neg()?),
+            ("⅟", [a]) => a.recip(),
+            ("÷", [a, b]) => a.mul(b.recip()?),
+            ("^", [a, b]) => a.pow(*b),
+            _ => None,
+        }
+    }
+
+    /// The numeral every expression with this value is made equivalent to.
+    pub fn numeral(self) -> String {
+        if self.den == 1 {
+            format!("{}", self.num)
+        } else {
+            format!("{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a.max(1)
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The e-graph analysis finding the value of each e-class, if it has one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Arithmetic {
+    pub enabled: bool,
+}
+
+impl egg::Analysis<Expression<egg::Id>> for Arithmetic {
+    type Data = Option<Rational>;
+
+    fn make(
+        egraph: &egg::EGraph<Expression<egg::Id>, Self>,
+        enode: &Expression<egg::Id>,
+    ) -> Self::Data {
+        if !egraph.analysis.enabled {
+            return None;
+        }
+        let Expression::Function(f, Type::RealNumber, inputs) = enode else {
+            return None;
+        };
+        let inputs = inputs
+            .iter()
+            .map(|id| egraph[*id].data)
+            .collect::<Option<Vec<_>>>()?;
+        Rational::apply(f, &inputs)
+    }
+
+    fn merge(&mut self, a: &mut Self::Data, b: Self::Data) -> egg::DidMerge {
+        // Values can only disagree in a case with contradictory hypotheses, where anything goes.
+        egg::merge_option(a, b, |a, b| egg::DidMerge(false, *a != b))
+    }
+
+    fn modify(egraph: &mut egg::EGraph<Expression<egg::Id>, Self>, id: egg::Id) {
+        if let Some(value) = egraph[id].data {
+            let numeral = egraph.add(Expression::Function(
+                value.numeral(),
+                Type::RealNumber,
+                Default::default(),
+            ));
+            egraph.union(id, numeral);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(s: &str) -> Rational {
+        Rational::parse(s).unwrap()
+    }
+
+    fn numeral(f: &str, inputs: &[&str]) -> Option<String> {
+        let inputs = inputs.iter().map(|x| q(x)).collect::<Vec<_>>();
+        Rational::apply(f, &inputs).map(Rational::numeral)
+    }
+
+    #[test]
+    fn parses_numerals_in_lowest_terms() {
+        for (numeral, value) in [
+            ("12", "12"),
+            ("-1", "-1"),
+            ("2.5", "5/2"),
+            ("0.50", "1/2"),
+            ("6/4", "3/2"),
+            ("½", "1/2"),
+            ("-¾", "-3/4"),
+        ] {
+            assert_eq!(q(numeral).numeral(), value, "{numeral}");
+        }
+    }
+
+    #[test]
+    fn rejects_everything_else() {
+        for text in [
+            "",
+            "-",
+            "--1",
+            "x",
+            "1/0",
+            "1/-2",
+            "1.",
+            ".5",
+            "1e3",
+            "½½",
+            "+1",
+            "99999999999999999999",
+        ] {
+            assert_eq!(Rational::parse(text), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn applies_basic_operations() {
+        assert_eq!(numeral("+", &["1", "2", "1/2"]).as_deref(), Some("7/2"));
+        assert_eq!(numeral("×", &["2/3", "3"]).as_deref(), Some("2"));
+        assert_eq!(numeral("-", &["2"]).as_deref(), Some("-2"));
+        assert_eq!(numeral("−", &["5", "7"]).as_deref(), Some("-2"));
+        assert_eq!(numeral("⅟", &["-4"]).as_deref(), Some("-1/4"));
+        assert_eq!(numeral("÷", &["1", "3"]).as_deref(), Some("1/3"));
+        assert_eq!(numeral("3/6", &[]).as_deref(), Some("1/2"));
+    }
+
+    #[test]
+    fn leaves_undefined_results_alone() {
+        assert_eq!(numeral("⅟", &["0"]), None);
+        assert_eq!(numeral("÷", &["1", "0"]), None);
+        assert_eq!(numeral("-", &["1", "2"]), None);
+        assert_eq!(numeral("√", &["4"]), None);
+        assert_eq!(numeral("x", &[]), None);
+    }
+
+    #[test]
+    fn powers() {
+        assert_eq!(numeral("^", &["2", "10"]).as_deref(), Some("1024"));
+        assert_eq!(numeral("^", &["2", "-2"]).as_deref(), Some("1/4"));
+        assert_eq!(numeral("^", &["-1/2", "3"]).as_deref(), Some("-1/8"));
+        assert_eq!(numeral("^", &["5", "0"]).as_deref(), Some("1"));
+        assert_eq!(numeral("^", &["0", "2"]).as_deref(), Some("0"));
+        assert_eq!(numeral("^", &["0", "0"]), None);
+        assert_eq!(numeral("^", &["0", "-1"]), None);
+        assert_eq!(numeral("^", &["4", "1/2"]), None);
+        assert_eq!(numeral("^", &["1", "65"]), None);
+        assert_eq!(numeral("^", &["2", "63"]), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Function symbols that the level pack defines in terms of others.
+//!
+//! A defined symbol can be unfolded into its definition, by clicking it,
+//! and an instance of a definition can be folded back into the symbol, by right-clicking its last node.
+//! Either way, the two are connected, since they're equal by definition.
+
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// Definitions, by the name of the function they define.
+pub type Definitions = BTreeMap<String, Definition>;
+
+/// `f(x₀, …, xₙ₋₁)` is defined to be the last expression of `body`.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub parameters: Vec<Var>,
+    /// Input `k` refers to the `k`th parameter if `k < parameters.len()`,
+    /// and to the expression at `body[k - parameters.len()]` otherwise.
+    /// Invariant: Every input refers to a parameter or an earlier expression.
+    pub body: Vec<Expression<usize>>,
+    /// Whether `wire_equiv` sees through the definition,
+    /// so that a use of the symbol can be connected straight to an unfolding of it.
+    pub transparent: bool,
+    /// The level that states the definition, if there is one.
+    /// Unfolding or folding the definition counts as applying that level's theorem.
+    pub level: Option<usize>,
+}
+
+impl Definition {
+    fn result(&self) -> usize {
+        self.parameters.len() + self.body.len() - 1
+    }
+
+    /// Whether `f(args)` can be unfolded by this definition.
+    pub fn applies(&self, case: &Case, args: &[Wire]) -> bool {
+        args.len() == self.parameters.len()
+            && args
+                .iter()
+                .zip(&self.parameters)
+                .all(|(&arg, Var(_, ty))| case.ty(arg) == *ty)
+    }
+
+    /// Whether `wire` carries this definition's body, connected the way it's written.
+    /// Parameters already in `bindings` must match; the rest are bound as they're found.
+    fn matches(&self, case: &Case, ix: usize, wire: Wire, bindings: &mut [Option<Wire>]) -> bool {
+        if ix < self.parameters.len() {
+            match bindings[ix] {
+                Some(bound) => case.wire_eq(bound, wire),
+                None if case.ty(wire) == self.parameters[ix].1 => {
+                    bindings[ix] = Some(wire);
+                    true
+                }
+                None => false,
+            }
+        } else {
+            case.wire_inputs(wire)
+                .any(|node| self.matches_node(case, ix, node, bindings))
+        }
+    }
+
+    fn matches_node(
+        &self,
+        case: &Case,
+        ix: usize,
+        node: Node,
+        bindings: &mut [Option<Wire>],
+    ) -> bool {
+        let pattern = &self.body[ix - self.parameters.len()];
+        let expression = case.node_expression(node);
+        if expression.clone().map(|_| ()) != pattern.clone().map(|_| ()) {
+            return false;
+        }
+
+        let saved = bindings.to_vec();
+        let matched = expression
+            .inputs()
+            .iter()
+            .zip(pattern.inputs())
+            .all(|(&wire, &ix)| self.matches(case, ix, wire, bindings));
+        if !matched {
+            bindings.copy_from_slice(&saved);
+        }
+        matched
+    }
+}
+
+impl Case {
+    /// The definition `node` can be unfolded by, if it hasn't been already.
+    pub fn unfolding(&self, node: Node) -> Option<&Definition> {
+        let Expression::Function(f, _, args) = self.node_expression(node) else {
+            return None;
+        };
+        let definition = self.definitions().get(f)?;
+        if !definition.applies(self, args) {
+            return None;
+        }
+
+        let mut bindings = args.iter().copied().map(Some).collect::<Vec<_>>();
+        let unfolded = definition.matches(
+            self,
+            definition.result(),
+            self.node_output(node),
+            &mut bindings,
+        );
+        (!unfolded).then_some(definition)
+    }
+
+    /// The function `node` can be folded into, along with its inputs,
+    /// if `node` is the last node of an instance of its definition, and it hasn't been folded already.
+    pub fn folding(&self, node: Node) -> Option<(&str, Vec<Wire>)> {
+        self.definitions().iter().find_map(|(f, definition)| {
+            let mut bindings = vec![None; definition.parameters.len()];
+            if !definition.matches_node(self, definition.result(), node, &mut bindings) {
+                return None;
+            }
+            // A parameter the body doesn't use can't be recovered.
+            let args = bindings.into_iter().collect::<Option<Vec<_>>>()?;
+
+            let folded = self.wire_inputs(self.node_output(node)).any(|n| {
+                matches!(
+                    self.node_expression(n),
+                    Expression::Function(g, _, inputs)
+                        if g == f
+                            && inputs.len() == args.len()
+                            && inputs.iter().zip(&args).all(|(&a, &b)| self.wire_eq(a, b))
+                )
+            });
+            (!folded).then_some((f.as_str(), args))
+        })
+    }
+}
+
+impl CaseTree {
+    /// Unfold a defined function into its definition.
+    /// It was checked in `node_has_interaction` that `Case::unfolding` is `Some`.
+    pub(super) fn unfold(&mut self, node: Node) {
+        let case = self.case(self.current).0;
+        let Expression::Function(_, _, args) = case.node_expression(node) else {
+            return;
+        };
+        let Some(definition) = case.unfolding(node) else {
+            return;
+        };
+        let args = args.clone();
+        let definition = definition.clone();
+        let [x, y] = case.position(node);
+        self.record_definition(definition.level);
+
+        // Lay the body out to the right of the node, each expression a row above those using it.
+        let p = definition.parameters.len();
+        let result = definition.result();
+        let mut depths = vec![0; p];
+        for expression in &definition.body {
+            let depth = expression.inputs().iter().map(|&ix| depths[ix]).max();
+            depths.push(depth.unwrap_or(0) + 1);
+        }
+        let result_depth = depths[result];
+        let mut columns = BTreeMap::new();
+
+        let mut case = self.current_case_mut();
+        let mut wires = args.into_vec();
+        for (k, expression) in definition.body.into_iter().enumerate() {
+            let depth = depths[p + k];
+            let column = columns.entry(depth).or_insert(0);
+            *column += 1;
+            let position = [
+                x + 1.5 * *column as f64,
+                y - 2.5 * (result_depth - depth) as f64,
+            ];
+            let new = case.make_node(expression.map(|ix| wires[ix]), position);
+            wires.push(case.node_output(new));
+        }
+
+        let output = case.node_output(node);
+        case.connect(
+            output,
+            wires[result],
+            ValidityReason::new("A function equals its definition."),
+        );
+    }
+
+    /// Fold an instance of a definition into the function it defines.
+    pub fn fold(&mut self, node: Node) {
+        let case = self.case(self.current).0;
+        let Some((f, args)) = case.folding(node) else {
+            return;
+        };
+        let level = case.definitions()[f].level;
+        let expression = Expression::Function(
+            f.to_owned(),
+            case.node_expression(node).ty(),
+            args.into_iter().collect(),
+        );
+        let [x, y] = case.position(node);
+        self.record_definition(level);
+
+        let mut case = self.current_case_mut();
+        let new = case.make_node(expression, [x - 1.5, y]);
+        let output = case.node_output(node);
+        let new_output = case.node_output(new);
+        case.connect(
+            output,
+            new_output,
+            ValidityReason::new(
+                "A function equals its definition. That the definition applies was checked in `folding`.",
+            ),
+        );
+    }
+}
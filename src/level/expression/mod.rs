@@ -1,3 +1,11 @@
+mod arithmetic;
+mod definition;
+mod rewrite;
+
+pub use arithmetic::{Arithmetic, Rational};
+pub use definition::{Definition, Definitions};
+pub use rewrite::{pattern, pattern_var, Equation, Rewrites};
+
 use super::case::*;
 use super::*;
 use smallvec::SmallVec;
@@ -15,7 +23,19 @@ pub enum Expression<T> {
 #[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Var(pub String, pub Type);
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize)]
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum Type {
     #[default]
     #[serde(rename = "Ω")]
@@ -78,6 +98,22 @@ impl<T> Expression<T> {
         }
     }
 
+    /// Write this expression as a formula, given how to write each of its inputs.
+    /// Symbolic functions of one input are written as prefixes, and of two inputs as infixes.
+    pub fn formula(&self, input: impl FnMut(&T) -> String) -> String {
+        let inputs = self.inputs().iter().map(input).collect::<Vec<_>>();
+        let symbolic = !self.text().chars().any(char::is_alphanumeric);
+        match (self, inputs.as_slice()) {
+            (_, []) => self.text().to_owned(),
+            (Expression::Function(..), [a]) if symbolic => format!("{}{a}", self.text()),
+            (Expression::Function(..), [_, _]) if symbolic => {
+                inputs.join(&format!(" {} ", self.text()))
+            }
+            (Expression::Function(..), _) => format!("{}({})", self.text(), inputs.join(", ")),
+            (_, _) => inputs.join(&format!(" {} ", self.text())),
+        }
+    }
+
     pub fn map<U>(self, f: impl FnMut(T) -> U) -> Expression<U> {
         match self {
             Expression::And(inputs) => Expression::And(inputs.into_iter().map(f).collect()),
@@ -132,10 +168,21 @@ impl Case {
             (Expression::Equal([w1, w2]), true) => !self.wire_eq(*w1, *w2),
             (Expression::Equal([w1, w2]), false) => self.wire_eq(*w1, *w2),
             (Expression::Variable(_), _) => false,
-            (Expression::Function(_, _, _), _) => false,
+            (Expression::Function(_, _, _), _) => self.unfolding(node).is_some(),
         }
     }
 
+    /// Whether `interact_node` would split the case.
+    pub fn node_interaction_splits(&self, node: Node) -> bool {
+        matches!(
+            (
+                self.node_expression(node),
+                self.proven(self.node_output(node))
+            ),
+            (Expression::Or(_), true) | (Expression::Implies(_), false)
+        )
+    }
+
     pub fn wire_has_interaction(&self, wire: Wire) -> bool {
         self.ty(wire) == Type::TruthValue && !self.proven(wire) && !self.wire_eq(wire, self.goal())
     }
@@ -243,10 +290,13 @@ So we might as well merge the wires.",
                 );
             }
             (Expression::Variable(_), _) => {}
-            (Expression::Function(_, _, _), _) => {}
+            (Expression::Function(_, _, _), _) => self.unfold(node),
         }
     }
 
+    /// Prove a lemma: first prove `wire`, then use it to prove the original goal.
+    /// This is the cut rule, which is intuitionistically valid,
+    /// so unlike the classical theorems, it remains available in constructive mode.
     pub fn interact_wire(&mut self, wire: Wire) {
         let mut subcases = [
             self.case(self.current).0.clone(),
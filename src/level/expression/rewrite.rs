@@ -0,0 +1,148 @@
+//! Equations the e-graph applies by itself, so that `wire_equiv` recognises more than congruence.
+//!
+//! The level pack declares them, each named after the level whose theorem it is,
+//! and each level chooses which of them are active.
+
+use std::{collections::HashMap, rc::Rc, time::Duration};
+
+use super::{Arithmetic, Expression, Var};
+
+/// `lhs = rhs`, for all values of the variables.
+#[derive(Debug, Clone)]
+pub struct Equation {
+    pub variables: Vec<Var>,
+    /// Numbered as in `Definition::body`, with the variables in place of the parameters.
+    pub body: Vec<Expression<usize>>,
+    pub lhs: usize,
+    pub rhs: usize,
+}
+
+type Rewrite = egg::Rewrite<Expression<egg::Id>, Arithmetic>;
+
+/// The rewrites active in a level, and whether it evaluates arithmetic on numerals.
+#[derive(Clone, Default)]
+pub struct Rewrites {
+    rules: Rc<Vec<Rewrite>>,
+    /// The levels whose theorems the rules are.
+    theorems: Vec<usize>,
+    evaluate: bool,
+}
+
+impl std::fmt::Debug for Rewrites {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rewrites")
+            .field("theorems", &self.theorems)
+            .field("evaluate", &self.evaluate)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Saturation stops after this many iterations, or this many new e-nodes, or this much time,
+/// since rules like associativity can go on forever.
+const ITER_LIMIT: usize = 8;
+const NODE_LIMIT: usize = 2_000;
+const TIME_LIMIT: Duration = Duration::from_millis(50);
+
+impl Rewrites {
+    /// Rewrites from equations, each paired with the level whose theorem it is.
+    /// An equation is used in each direction where every variable of the result is bound by the pattern,
+    /// and the pattern isn't a lone variable.
+    pub fn new<'a>(
+        equations: impl IntoIterator<Item = (usize, &'a Equation)>,
+        evaluate: bool,
+    ) -> Self {
+        let mut rules = Vec::new();
+        let mut theorems = Vec::new();
+        for (theorem, equation) in equations {
+            theorems.push(theorem);
+            for (name, from, to) in [
+                (format!("{theorem}"), equation.lhs, equation.rhs),
+                (format!("{theorem}-rev"), equation.rhs, equation.lhs),
+            ] {
+                if from < equation.variables.len() {
+                    continue;
+                }
+                if let Ok(rule) = Rewrite::new(name, equation.pattern(from), equation.pattern(to)) {
+                    rules.push(rule);
+                }
+            }
+        }
+        Self {
+            rules: Rc::new(rules),
+            theorems,
+            evaluate,
+        }
+    }
+
+    pub fn theorems(&self) -> &[usize] {
+        &self.theorems
+    }
+
+    /// The analysis for the level's e-graphs.
+    pub fn analysis(&self) -> Arithmetic {
+        Arithmetic {
+            enabled: self.evaluate,
+        }
+    }
+
+    /// Apply the rules until nothing changes, or a limit is reached.
+    pub fn saturate(&self, egraph: &mut egg::EGraph<Expression<egg::Id>, Arithmetic>) {
+        if self.rules.is_empty() {
+            return;
+        }
+        let runner = egg::Runner::<_, _, ()>::new(self.analysis())
+            .with_iter_limit(ITER_LIMIT)
+            .with_node_limit(egraph.total_size() + NODE_LIMIT)
+            .with_time_limit(TIME_LIMIT)
+            .with_egraph(std::mem::take(egraph))
+            .run(self.rules.iter());
+        *egraph = runner.egraph;
+    }
+}
+
+impl Equation {
+    fn pattern(&self, root: usize) -> egg::Pattern<Expression<egg::Id>> {
+        pattern(root, |ix| match ix.checked_sub(self.variables.len()) {
+            None => Expression::Variable(self.variables[ix].clone()),
+            Some(k) => self.body[k].clone(),
+        })
+    }
+}
+
+/// The pattern variable standing for `v`.
+pub fn pattern_var(v: &Var) -> egg::Var {
+    format!("?{}", v.0)
+        .parse()
+        .expect("Pattern variables start with `?`.")
+}
+
+/// The pattern matching the expression at `root`, where `node` gives the expression at each index,
+/// and variables match anything.
+pub fn pattern(
+    root: usize,
+    node: impl Fn(usize) -> Expression<usize>,
+) -> egg::Pattern<Expression<egg::Id>> {
+    fn add(
+        ix: usize,
+        node: &impl Fn(usize) -> Expression<usize>,
+        ast: &mut egg::PatternAst<Expression<egg::Id>>,
+        added: &mut HashMap<usize, egg::Id>,
+    ) -> egg::Id {
+        if let Some(&id) = added.get(&ix) {
+            return id;
+        }
+        let id = match node(ix) {
+            Expression::Variable(v) => ast.add(egg::ENodeOrVar::Var(pattern_var(&v))),
+            expression => {
+                let expression = expression.map(|input| add(input, node, ast, added));
+                ast.add(egg::ENodeOrVar::ENode(expression))
+            }
+        };
+        added.insert(ix, id);
+        id
+    }
+
+    let mut ast = egg::RecExpr::default();
+    add(root, &node, &mut ast, &mut HashMap::new());
+    egg::Pattern::new(ast)
+}
@@ -4,38 +4,96 @@ mod case;
 mod case_tree;
 pub mod expression;
 mod render;
+pub mod tutorial;
 
 use std::collections::HashMap;
 
-pub use case::LevelSpec;
+pub(crate) use case::{render_node, render_wire, Fnv};
+pub use case::{LevelSpec, Propositional};
 
 use crate::{game_data::Unlocks, render::PanZoom};
-use case::{Case, Node, ValidityReason, Wire};
+use case::{Case, Counterexample, Node, Part, ValidityReason, Wire};
 use case_tree::{CaseId, CaseTree};
+use tutorial::Action;
 
 pub struct State {
     pub case_tree: CaseTree,
     pan_zoom: PanZoom,
-    text_box: Option<(String, Option<crate::book::BookPage>)>,
+    /// The text box, and the id of the book page it links to.
+    text_box: Option<(crate::locale::Translated, Option<String>)>,
+    /// The id of the book's table of contents, if the pack has a book.
+    contents: Option<String>,
+    tutorial: Vec<tutorial::Step>,
+    /// How many steps of the tutorial have been completed.
+    tutorial_step: usize,
     drag: Option<DragState>,
     unlocks: Unlocks,
     axiom: bool,
+    /// Whether the level is being play-tested from the editor, rather than embedded in the book.
+    /// Only matters for levels without a number.
+    playtest: bool,
     mode: Option<Mode>,
     last_recorded_mouse_position: [f64; 2],
+    stats: Stats,
+    /// Timestamps, in milliseconds, of when the attempt started and when the level was completed.
+    started_at: f64,
+    completed_at: Option<f64>,
+    /// The theorems applied in the proof, as of when the level was completed.
+    theorems_used: Vec<usize>,
+    /// The last search for a counterexample to the current case's goal, until the case changes.
+    /// `Some(None)` if the search found nothing.
+    counterexample: Option<Option<Counterexample>>,
+    /// The current case and the case tree, as last rendered.
+    /// Moving the mouse while applying a theorem only moves the theorem's ghost, so leaves these alone.
+    case_layer: crate::render::Layer<render::CaseView>,
+    case_tree_layer: crate::render::Layer<render::CaseTreeView>,
+}
+
+/// What the player did during one attempt at a level.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct Stats {
+    /// Node and wire interactions, connections, deletions and restorations.
+    pub interactions: u32,
+    pub theorem_applications: u32,
+    /// The interactions that split the case in two or more.
+    pub case_splits: u32,
+    pub reverts: u32,
+    pub seconds: f64,
+}
+
+impl Stats {
+    /// The number that par values are compared against.
+    pub fn moves(&self) -> u32 {
+        self.interactions + self.theorem_applications
+    }
+
+    /// Fewer moves is better; ties are broken by time.
+    pub fn better_than(&self, other: &Self) -> bool {
+        (self.moves(), self.seconds) < (other.moves(), other.seconds)
+    }
 }
 
 enum Mode {
-    ChooseTheoremLocation(LevelSpec),
+    /// The `usize` is the level whose theorem is being applied.
+    ChooseTheoremLocation(usize, LevelSpec),
     AssignTheoremVars {
+        theorem: usize,
         spec: LevelSpec,
         offset: [f64; 2],
         chosen: HashMap<expression::Var, Node>,
         current: expression::Var,
         remaining: std::vec::IntoIter<expression::Var>,
+        /// Whether the theorem's conclusion was matched to the goal,
+        /// so that it's applied backward, replacing the goal with the hypotheses.
+        backward: bool,
     },
     SelectUndo {
         preview: CaseId,
     },
+    /// The player dropped an important node on the trash can, and must confirm the deletion.
+    ConfirmDelete(Node),
+    /// The trash can is open, listing the deleted nodes of the current case.
+    Trash,
 }
 
 #[derive(Clone, Copy)]
@@ -55,12 +113,23 @@ pub enum Msg {
     MouseUp(f64, f64, Option<DropObject>),
     MouseWheel(f64, f64, f64),
     GotoCase(CaseId),
+    /// Fold an instance of a definition into the function it defines.
+    FoldNode(Node),
 
-    SelectedTheorem(LevelSpec),
+    SelectedTheorem(usize, LevelSpec),
     Cancel,
 
     RevertPreview(CaseId),
     RevertTo(CaseId),
+
+    OpenTrash,
+    DeleteNode(Node),
+    RestoreNode(Node),
+
+    /// Search for values under which the current case's facts hold but its goal doesn't.
+    FindCounterexample,
+
+    TutorialNext,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -80,7 +149,9 @@ impl State {
     pub fn new(
         spec: &LevelSpec,
         pan_zoom: PanZoom,
-        text_box: Option<(String, Option<crate::book::BookPage>)>,
+        text_box: Option<(crate::locale::Translated, Option<String>)>,
+        contents: Option<String>,
+        tutorial: Vec<tutorial::Step>,
         unlocks: Unlocks,
         axiom: bool,
     ) -> Self {
@@ -88,11 +159,54 @@ impl State {
             case_tree: CaseTree::new(spec.to_case([0., 0.])),
             pan_zoom,
             text_box,
+            contents,
+            tutorial,
+            tutorial_step: 0,
             drag: None,
             unlocks,
             axiom,
+            playtest: false,
             mode: None,
             last_recorded_mouse_position: [0., 0.],
+            stats: Stats::default(),
+            started_at: js_sys::Date::now(),
+            completed_at: None,
+            theorems_used: Vec::new(),
+            counterexample: None,
+            case_layer: crate::render::Layer::new(),
+            case_tree_layer: crate::render::Layer::new(),
+        }
+    }
+
+    /// A level from the editor, to try out with everything unlocked.
+    pub fn playtest(spec: &LevelSpec, pan_zoom: PanZoom) -> Self {
+        Self {
+            playtest: true,
+            ..Self::new(spec, pan_zoom, None, None, Vec::new(), Unlocks::ALL, false)
+        }
+    }
+
+    /// Statistics of this attempt. Once the level is complete, they stop changing.
+    pub fn stats(&self) -> Stats {
+        Stats {
+            seconds: (self.completed_at.unwrap_or_else(js_sys::Date::now) - self.started_at)
+                / 1000.,
+            ..self.stats
+        }
+    }
+
+    /// The levels whose theorems the completed proof applies.
+    pub fn theorems_used(&self) -> &[usize] {
+        &self.theorems_used
+    }
+
+    /// Must be called *before* the counted action is performed,
+    /// so that the action which completes the level is still counted.
+    /// Every counted action changes the case, so a counterexample found before it no longer applies.
+    fn count(&mut self, f: impl FnOnce(&mut Stats)) {
+        self.counterexample = None;
+        if !self.complete() {
+            f(&mut self.stats)
         }
     }
 
@@ -101,6 +215,20 @@ impl State {
     }
 
     pub fn update(&mut self, msg: Msg, rerender: &mut bool) {
+        if !matches!(msg, Msg::MouseMove(..)) {
+            self.redraw();
+        }
+        self.update_inner(msg, rerender);
+        if self.complete() && self.tutorial_action() == Some(Action::Complete) {
+            self.tutorial_step += 1;
+        }
+        if self.completed_at.is_none() && self.complete() {
+            self.completed_at = Some(js_sys::Date::now());
+            self.theorems_used = self.case_tree.theorems_used().into_iter().collect();
+        }
+    }
+
+    fn update_inner(&mut self, msg: Msg, rerender: &mut bool) {
         match msg {
             Msg::MouseDown(x, y, object) => {
                 self.mouse_move(x, y, rerender);
@@ -128,11 +256,20 @@ impl State {
                         if let DragObject::Node(n1) = object {
                             match dropped_on {
                                 Some(DropObject::Node(n2)) => {
-                                    let mut case = self.case_tree.current_case_mut();
+                                    let case = self.case_tree.case(self.case_tree.current).0;
                                     let w1 = case.node_output(n1);
                                     let w2 = case.node_output(n2);
                                     if case.wire_equiv(w1, w2) {
-                                        case.connect(
+                                        if !case.wire_eq(w1, w2) {
+                                            self.count(|stats| stats.interactions += 1);
+                                        }
+                                        if let Some(Action::Drag(a, b)) = self.tutorial_action() {
+                                            let [a, b] = [a, b].map(Node::from_spec);
+                                            if (a, b) == (n1, n2) || (b, a) == (n1, n2) {
+                                                self.tutorial_step += 1;
+                                            }
+                                        }
+                                        self.case_tree.current_case_mut().connect(
                                             w1,
                                             w2,
                                             ValidityReason::new("I just checked equivalence."),
@@ -141,7 +278,15 @@ impl State {
                                     *rerender = true;
                                 }
                                 Some(DropObject::TrashCan) => {
-                                    self.case_tree.current_case_mut().set_deleted(n1);
+                                    let case = self.case_tree.case(self.case_tree.current).0;
+                                    let w1 = case.node_output(n1);
+                                    if case.proven(w1) || case.wire_eq(w1, case.goal()) {
+                                        self.mode = Some(Mode::ConfirmDelete(n1));
+                                    } else {
+                                        self.count(|stats| stats.interactions += 1);
+                                        self.case_tree.current_case_mut().set_deleted(n1);
+                                    }
+                                    *rerender = true;
                                 }
                                 None => {}
                             }
@@ -151,25 +296,53 @@ impl State {
                     // This is a click.
                     if self.interactable() {
                         match self.mode.take() {
-                            Some(Mode::ChooseTheoremLocation(spec)) => {
+                            Some(Mode::ChooseTheoremLocation(theorem, spec)) => {
+                                // Dropping the theorem on a node fills in what variables it can.
+                                // Dropping it on the goal wire applies it backward,
+                                // if its conclusion matches the goal.
+                                let case = self.case_tree.case(self.case_tree.current).0;
+                                let (chosen, backward) = match object {
+                                    DragObject::Node(node) => {
+                                        (case.match_theorem(&spec, spec.patterns(), node), false)
+                                    }
+                                    DragObject::Wire(wire) if case.wire_eq(wire, case.goal()) => {
+                                        match case.match_goal(&spec) {
+                                            Some(chosen) => (chosen, true),
+                                            None => {
+                                                self.mode = Some(Mode::ChooseTheoremLocation(
+                                                    theorem, spec,
+                                                ));
+                                                self.drag = None;
+                                                return;
+                                            }
+                                        }
+                                    }
+                                    DragObject::Wire(_) | DragObject::Background => {
+                                        (HashMap::new(), false)
+                                    }
+                                };
                                 self.start_processing_var(Mode::AssignTheoremVars {
+                                    theorem,
                                     offset: self.last_recorded_mouse_position,
-                                    chosen: HashMap::new(),
+                                    chosen,
                                     current: Default::default(),
                                     remaining: spec
                                         .vars()
                                         .collect::<Vec<expression::Var>>()
                                         .into_iter(),
                                     spec,
+                                    backward,
                                 });
                                 *rerender = true;
                             }
                             Some(Mode::AssignTheoremVars {
+                                theorem,
                                 spec,
                                 offset,
                                 mut chosen,
                                 current,
                                 remaining,
+                                backward,
                             }) => {
                                 let case = self.case_tree.case(self.case_tree.current).0;
                                 match object {
@@ -178,11 +351,13 @@ impl State {
                                     {
                                         chosen.insert(current, n);
                                         self.start_processing_var(Mode::AssignTheoremVars {
+                                            theorem,
                                             spec,
                                             offset,
                                             chosen,
                                             current: Default::default(),
                                             remaining,
+                                            backward,
                                         });
                                         *rerender = true;
                                     }
@@ -190,22 +365,36 @@ impl State {
                                     | DragObject::Wire(_)
                                     | DragObject::Background => {
                                         self.mode = Some(Mode::AssignTheoremVars {
+                                            theorem,
                                             spec,
                                             offset,
                                             chosen,
                                             current,
                                             remaining,
+                                            backward,
                                         })
                                     }
                                 }
                             }
-                            Some(Mode::SelectUndo { preview }) => {
-                                self.mode = Some(Mode::SelectUndo { preview })
-                            }
+                            Some(
+                                mode @ (Mode::SelectUndo { .. }
+                                | Mode::ConfirmDelete(_)
+                                | Mode::Trash),
+                            ) => self.mode = Some(mode),
                             None => match object {
                                 DragObject::Node(node) => {
                                     let case = self.case_tree.case(self.case_tree.current).0;
                                     if case.node_has_interaction(node) {
+                                        let splits = case.node_interaction_splits(node);
+                                        self.count(|stats| {
+                                            stats.interactions += 1;
+                                            stats.case_splits += splits as u32;
+                                        });
+                                        if let Some(Action::Click(n)) = self.tutorial_action() {
+                                            if Node::from_spec(n) == node {
+                                                self.tutorial_step += 1;
+                                            }
+                                        }
                                         self.case_tree.interact_node(node);
                                         *rerender = true;
                                     }
@@ -215,6 +404,17 @@ impl State {
                                     if self.unlocks >= Unlocks::LEMMAS
                                         && case.wire_has_interaction(wire)
                                     {
+                                        if let Some(Action::ClickWire(n)) = self.tutorial_action() {
+                                            if case
+                                                .wire_eq(case.node_output(Node::from_spec(n)), wire)
+                                            {
+                                                self.tutorial_step += 1;
+                                            }
+                                        }
+                                        self.count(|stats| {
+                                            stats.interactions += 1;
+                                            stats.case_splits += 1;
+                                        });
                                         self.case_tree.interact_wire(wire);
                                         *rerender = true;
                                     }
@@ -246,15 +446,29 @@ impl State {
 
                 *rerender = true
             }
+            Msg::FoldNode(node) => {
+                let case = self.case_tree.case(self.case_tree.current).0;
+                if self.interactable() && self.mode.is_none() && case.folding(node).is_some() {
+                    self.count(|stats| stats.interactions += 1);
+                    self.case_tree.fold(node);
+                    *rerender = true;
+                }
+            }
             Msg::GotoCase(id) => {
                 self.case_tree.current = id;
                 self.mode = None;
+                self.counterexample = None;
+                *rerender = true
+            }
+            Msg::FindCounterexample => {
+                let case = self.case_tree.case(self.case_tree.current).0;
+                self.counterexample = Some(case.counterexample());
                 *rerender = true
             }
 
             // Theorem application
-            Msg::SelectedTheorem(spec) => {
-                self.mode = Some(Mode::ChooseTheoremLocation(spec));
+            Msg::SelectedTheorem(theorem, spec) => {
+                self.mode = Some(Mode::ChooseTheoremLocation(theorem, spec));
                 *rerender = true
             }
             Msg::RevertPreview(preview) => {
@@ -262,6 +476,7 @@ impl State {
                 *rerender = true
             }
             Msg::RevertTo(case) => {
+                self.count(|stats| stats.reverts += 1);
                 self.mode = None;
                 self.case_tree.revert_to(case);
                 *rerender = true
@@ -270,21 +485,60 @@ impl State {
                 self.mode = None;
                 *rerender = true
             }
+
+            // Trash can
+            Msg::OpenTrash => {
+                if self.interactable() {
+                    self.mode = Some(Mode::Trash);
+                    *rerender = true
+                }
+            }
+            Msg::DeleteNode(node) => {
+                self.count(|stats| stats.interactions += 1);
+                self.mode = None;
+                self.case_tree.current_case_mut().set_deleted(node);
+                *rerender = true
+            }
+            Msg::RestoreNode(node) => {
+                self.count(|stats| stats.interactions += 1);
+                self.case_tree.current_case_mut().restore(node);
+                *rerender = true
+            }
+
+            Msg::TutorialNext => {
+                if self.tutorial_action() == Some(Action::Next) {
+                    self.tutorial_step += 1;
+                    *rerender = true
+                }
+            }
         }
     }
 
     fn start_processing_var(&mut self, theorem_application: Mode) {
-        let Mode::AssignTheoremVars { spec, offset, chosen, current: _, mut remaining } = theorem_application else {return};
+        let Mode::AssignTheoremVars {
+            theorem,
+            spec,
+            offset,
+            chosen,
+            current: _,
+            mut remaining,
+            backward,
+        } = theorem_application
+        else {
+            return;
+        };
         for v in remaining.by_ref() {
             if chosen.contains_key(&v) {
                 continue;
             } else {
                 self.mode = Some(Mode::AssignTheoremVars {
+                    theorem,
                     spec,
                     offset,
                     chosen,
                     current: v,
                     remaining,
+                    backward,
                 });
                 return;
             }
@@ -293,7 +547,48 @@ impl State {
         // If control reaches here, all variables have been chosen.
 
         self.mode = None;
-        spec.add_to_case_tree(&mut self.case_tree, move |v| chosen[v], offset)
+        self.count(|stats| stats.theorem_applications += 1);
+        if self.tutorial_action() == Some(Action::ApplyTheorem) {
+            self.tutorial_step += 1;
+        }
+        spec.add_to_case_tree(
+            &mut self.case_tree,
+            theorem,
+            move |v| chosen[v],
+            offset,
+            backward,
+        )
+    }
+
+    /// The current step of the tutorial, if it isn't over.
+    fn tutorial(&self) -> Option<&tutorial::Step> {
+        self.tutorial.get(self.tutorial_step)
+    }
+
+    fn tutorial_action(&self) -> Option<Action> {
+        self.tutorial().map(|step| step.advance)
+    }
+
+    /// Called when the player opens the theorem selection screen.
+    pub fn opened_theorem_select(&mut self) {
+        if self.tutorial_action() == Some(Action::SelectTheorem) {
+            self.tutorial_step += 1;
+            self.redraw();
+        }
+    }
+
+    /// Whether a theorem could be applied backward to the goal of the current case.
+    pub fn theorem_matches_goal(&self, spec: &LevelSpec) -> bool {
+        self.case_tree
+            .case(self.case_tree.current)
+            .0
+            .goal_matches(spec)
+    }
+
+    /// Mark the cached parts of the view as stale.
+    fn redraw(&self) {
+        self.case_layer.invalidate();
+        self.case_tree_layer.invalidate();
     }
 
     pub fn complete(&self) -> bool {
@@ -320,6 +615,8 @@ impl State {
         if let Err(init_coord) = confirmed_drag {
             if (coord.0 - init_coord.0).powi(2) + (coord.1 - init_coord.1).powi(2) > 0.01 {
                 *confirmed_drag = Ok(());
+                // The dragged node and its wires are drawn apart from the rest from now on.
+                self.case_layer.invalidate();
             }
         }
 
@@ -1,7 +1,11 @@
+use super::expression::Type;
 use super::*;
 use crate::architecture::Architecture;
 use crate::game_data::Unlocks;
+use crate::locale::{Strings, Text};
+use crate::render::bezier::WireStyle;
 use crate::render::g;
+use crate::render::text_;
 use crate::render::to_svg_coords;
 use crate::Model;
 use dodrio::builder::*;
@@ -9,21 +13,73 @@ use dodrio::bumpalo;
 
 use wasm_bindgen::JsCast;
 
+/// The current case, along with everything else it takes to render it.
+/// A node being dragged, and its wires, are left out, so the drag doesn't redraw the rest.
+#[derive(Default)]
+pub(super) struct CaseView {
+    case: Option<Case>,
+    dragging: Option<Node>,
+    highlight: Option<tutorial::Step>,
+    wire_style: WireStyle,
+    /// While assigning a theorem's variables, the type of the current variable.
+    assigning: Option<Type>,
+    interactive: bool,
+    lemmas: bool,
+}
+
+impl<'a> dodrio::Render<'a> for CaseView {
+    fn render(&self, cx: &mut dodrio::RenderContext<'a>) -> dodrio::Node<'a> {
+        let Some(case) = &self.case else {
+            return g(cx.bump).finish();
+        };
+        let [wires, nodes] = case.render(
+            cx,
+            self.dragging,
+            Part::Still,
+            true,
+            self.highlight.as_ref(),
+            self.wire_style,
+            |node| match self.assigning {
+                Some(ty) => ty == case.ty(case.node_output(node)),
+                None => self.interactive && case.node_has_interaction(node),
+            },
+            |wire| self.lemmas && self.interactive && case.wire_has_interaction(wire),
+        );
+        g(cx.bump).children([wires, nodes]).finish()
+    }
+}
+
+#[derive(Default)]
+pub(super) struct CaseTreeView {
+    case_tree: Option<CaseTree>,
+    undo_buttons: bool,
+    axiom: bool,
+}
+
+impl<'a> dodrio::Render<'a> for CaseTreeView {
+    fn render(&self, cx: &mut dodrio::RenderContext<'a>) -> dodrio::Node<'a> {
+        match &self.case_tree {
+            Some(case_tree) => case_tree.render(cx, self.undo_buttons, self.axiom),
+            None => div(cx.bump).finish(),
+        }
+    }
+}
+
 impl State {
     fn main_screen<'a>(
         &self,
         cx: &mut dodrio::RenderContext<'a>,
+        wire_style: WireStyle,
     ) -> dodrio::builder::ElementBuilder<
         'a,
         [dodrio::Listener<'a>; 4],
-        [dodrio::Attribute<'a>; 4],
+        [dodrio::Attribute<'a>; 3],
         dodrio::bumpalo::collections::Vec<'a, dodrio::Node<'a>>,
     > {
         let (case, complete) = self.case_tree.case(self.case_tree.current);
 
         let mut main_screen = svg(cx.bump)
             .attributes([
-                attr("id", "game"),
                 attr(
                     "class",
                     if self.axiom {
@@ -41,59 +97,100 @@ impl State {
             ])
             .listeners([
                 Model::listener(cx.bump, "mousedown", move |e| {
-                    let (x, y) =
-                        to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "game");
+                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
                     crate::Msg::Level(Msg::MouseDown(x, y, DragObject::Background))
                 }),
                 Model::listener(cx.bump, "mouseup", move |e| {
-                    let (x, y) =
-                        to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "game");
+                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
                     crate::Msg::Level(Msg::MouseUp(x, y, None))
                 }),
                 Model::listener(cx.bump, "mousemove", move |e| {
-                    let (x, y) =
-                        to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "game");
+                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
                     crate::Msg::Level(Msg::MouseMove(x, y))
                 }),
                 Model::listener(cx.bump, "wheel", move |e| {
                     let e = e.dyn_into::<web_sys::WheelEvent>().unwrap();
                     let wheel = e.delta_y();
-                    let (x, y) = to_svg_coords(e.into(), "game");
+                    let (x, y) = to_svg_coords(e.into());
                     crate::Msg::Level(Msg::MouseWheel(x, y, wheel))
                 }),
             ]);
 
-        let [wires0, nodes0] = case.render(
-            cx,
-            match self.drag {
-                Some(DragState {
-                    object: DragObject::Node(node),
-                    confirmed_drag: Ok(()),
-                    ..
-                }) => Some(node),
+        if self.case_layer.contents().wire_style != wire_style {
+            self.case_layer.invalidate();
+        }
+        let dragging = match self.drag {
+            Some(DragState {
+                object: DragObject::Node(node),
+                confirmed_drag: Ok(()),
+                ..
+            }) => Some(node),
+            _ => None,
+        };
+        let highlight = self.tutorial().filter(|step| step.highlights());
+        let still = self.case_layer.render(cx, || CaseView {
+            case: Some(case.clone()),
+            dragging,
+            highlight: highlight.cloned(),
+            wire_style,
+            assigning: match &self.mode {
+                Some(Mode::AssignTheoremVars { current, .. }) => Some(current.1),
                 _ => None,
             },
-            true,
-            |node| match &self.mode {
-                Some(Mode::AssignTheoremVars { current, .. }) => {
-                    current.1 == case.ty(case.node_output(node))
-                }
-                Some(Mode::ChooseTheoremLocation(_)) => false,
-                Some(Mode::SelectUndo { .. }) => false,
-                None => self.interactable() && case.node_has_interaction(node),
-            },
-            |wire| {
-                self.unlocks >= Unlocks::LEMMAS
-                    && self.mode.is_none()
-                    && self.interactable()
-                    && case.wire_has_interaction(wire)
-            },
-        );
-        main_screen = main_screen.child(wires0).child(nodes0);
+            interactive: self.mode.is_none() && self.interactable(),
+            lemmas: self.unlocks >= Unlocks::LEMMAS,
+        });
+        main_screen = main_screen.child(still);
+        if dragging.is_some() {
+            let [wires, nodes] = case.render(
+                cx,
+                dragging,
+                Part::Moving,
+                true,
+                highlight,
+                wire_style,
+                |_| false,
+                |_| false,
+            );
+            main_screen = main_screen.child(g(cx.bump).children([wires, nodes]).finish());
+        }
+
+        // The counterexample's value of each node, beside the node.
+        if let Some(Some(counterexample)) = &self.counterexample {
+            let case = self.case_tree.case(self.case_tree.current).0;
+            let mut values = g(cx.bump).attributes([attr("class", "counterexample")]);
+            for node in case.nodes() {
+                let [x, y] = case.position(node);
+                values = values.child(
+                    text_(cx.bump)
+                        .attributes([
+                            attr(
+                                "x",
+                                bumpalo::format!(in cx.bump, "{}", x + 0.5).into_bump_str(),
+                            ),
+                            attr(
+                                "y",
+                                bumpalo::format!(in cx.bump, "{}", y - 0.5).into_bump_str(),
+                            ),
+                        ])
+                        .children([text(
+                            bumpalo::format!(in cx.bump, "{}", counterexample.value(node))
+                                .into_bump_str(),
+                        )])
+                        .finish(),
+                );
+            }
+            main_screen = main_screen.child(values.finish());
+        }
         main_screen
     }
 
-    fn preview<'a>(&self, cx: &mut dodrio::RenderContext<'a>, case: &Case) -> dodrio::Node<'a> {
+    fn preview<'a>(
+        &self,
+        cx: &mut dodrio::RenderContext<'a>,
+        case: &Case,
+        wire_style: WireStyle,
+    ) -> dodrio::Node<'a> {
         let [wires0, nodes0] = case.render(
             cx,
             match self.drag {
@@ -104,13 +201,15 @@ impl State {
                 }) => Some(node),
                 _ => None,
             },
+            Part::Everything,
             false,
+            None,
+            wire_style,
             |_| false,
             |_| false,
         );
         svg(cx.bump)
             .attributes([
-                attr("id", "game"),
                 attr("class", "background disabled"),
                 attr("preserveAspectRatio", "xMidYMid meet"),
                 self.pan_zoom.viewbox(cx.bump),
@@ -120,10 +219,99 @@ impl State {
             .finish()
     }
 
+    fn confirm_delete<'a>(
+        &self,
+        cx: &mut dodrio::RenderContext<'a>,
+        strings: Strings,
+        node: Node,
+    ) -> dodrio::Node<'a> {
+        let case = self.case_tree.case(self.case_tree.current).0;
+        let output = case.node_output(node);
+        div(cx.bump)
+            .attributes([attr("class", "trash-list background")])
+            .children([
+                text(strings.get_in(
+                    cx.bump,
+                    if case.wire_eq(output, case.goal()) {
+                        Text::NodeHoldsGoal
+                    } else {
+                        Text::NodeHoldsFact
+                    },
+                )),
+                div(cx.bump)
+                    .attributes([attr("class", "button red")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::Level(Msg::DeleteNode(node))
+                    })])
+                    .children([text(strings.get_in(cx.bump, Text::Delete))])
+                    .finish(),
+                div(cx.bump)
+                    .attributes([attr("class", "button blue")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::Level(Msg::Cancel)
+                    })])
+                    .children([text(strings.get_in(cx.bump, Text::Keep))])
+                    .finish(),
+            ])
+            .finish()
+    }
+
+    fn trash<'a>(&self, cx: &mut dodrio::RenderContext<'a>, strings: Strings) -> dodrio::Node<'a> {
+        let case = self.case_tree.case(self.case_tree.current).0;
+
+        let mut builder = div(cx.bump)
+            .attributes([attr("class", "trash-list background")])
+            .child(text(strings.get_in(cx.bump, Text::DeletedNodes)));
+
+        let mut empty = true;
+        for node in case.deleted_nodes() {
+            empty = false;
+            builder = builder.child(
+                div(cx.bump)
+                    .attributes([attr("class", "button blue")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::Level(Msg::RestoreNode(node))
+                    })])
+                    .children([text(
+                        bumpalo::collections::String::from_str_in(
+                            &case.formula(case.node_output(node)),
+                            cx.bump,
+                        )
+                        .into_bump_str(),
+                    )])
+                    .finish(),
+            );
+        }
+        if empty {
+            builder = builder.child(
+                div(cx.bump)
+                    .attributes([attr("class", "button disabled")])
+                    .children([text(strings.get_in(cx.bump, Text::TrashEmpty))])
+                    .finish(),
+            );
+        }
+
+        builder
+            .child(
+                div(cx.bump)
+                    .attributes([attr("class", "button yellow")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::Level(Msg::Cancel)
+                    })])
+                    .children([text(strings.get_in(cx.bump, Text::Close))])
+                    .finish(),
+            )
+            .finish()
+    }
+
+    /// Render the level. `current_level` is `None` for a sandbox embedded in the book, or a play-test in the editor,
+    /// which have no place on the map, and can't apply theorems.
     pub fn render<'a>(
         &self,
         cx: &mut dodrio::RenderContext<'a>,
-        current_level: usize,
+        strings: Strings,
+        wire_style: WireStyle,
+        current_level: Option<usize>,
         next_level: Option<usize>,
     ) -> [dodrio::Node<'a>; 2] {
         let mut col0 = div(cx.bump).attributes([attr("class", "col wide")]);
@@ -131,11 +319,13 @@ impl State {
 
         // Main Screen
         let main_screen = match &self.mode {
-            None => self.main_screen(cx).finish(),
-            Some(Mode::ChooseTheoremLocation(spec)) => {
+            None | Some(Mode::ConfirmDelete(_) | Mode::Trash) => {
+                self.main_screen(cx, wire_style).finish()
+            }
+            Some(Mode::ChooseTheoremLocation(_, spec)) => {
                 let [wires1, nodes1] = spec.render(cx, self.last_recorded_mouse_position, |_| None);
 
-                self.main_screen(cx)
+                self.main_screen(cx, wire_style)
                     .child(
                         g(cx.bump)
                             .attributes([attr("style", "opacity: 0.5; pointer-events: none;")])
@@ -146,11 +336,13 @@ impl State {
                     .finish()
             }
             Some(Mode::AssignTheoremVars {
+                theorem: _,
                 spec,
                 offset,
                 chosen,
                 current,
                 remaining: _,
+                backward: _,
             }) => {
                 let [wires1, nodes1] = spec.render(cx, *offset, |v| {
                     if v == current {
@@ -162,7 +354,7 @@ impl State {
                     }
                 });
 
-                self.main_screen(cx)
+                self.main_screen(cx, wire_style)
                     .child(
                         g(cx.bump)
                             .attributes([attr("style", "opacity: 0.5; pointer-events: none;")])
@@ -172,7 +364,9 @@ impl State {
                     )
                     .finish()
             }
-            Some(Mode::SelectUndo { preview }) => self.preview(cx, self.case_tree.case(*preview).0),
+            Some(Mode::SelectUndo { preview }) => {
+                self.preview(cx, self.case_tree.case(*preview).0, wire_style)
+            }
         };
         col0 = col0.child({
             let mut tmp = div(cx.bump)
@@ -182,23 +376,61 @@ impl State {
                 tmp = tmp.child({
                     div(cx.bump)
                         .attributes([attr("class", "trash-can")])
-                        .listeners([Model::listener(cx.bump, "mouseup", |_| {
-                            crate::Msg::Level(Msg::MouseUp(0., 0., Some(DropObject::TrashCan)))
-                        })])
+                        .listeners([
+                            Model::listener(cx.bump, "mouseup", |_| {
+                                crate::Msg::Level(Msg::MouseUp(0., 0., Some(DropObject::TrashCan)))
+                            }),
+                            Model::listener(cx.bump, "click", |_| {
+                                crate::Msg::Level(Msg::OpenTrash)
+                            }),
+                        ])
                         .child(text("🗑"))
                         .finish()
                 });
             }
+            match self.mode {
+                Some(Mode::ConfirmDelete(node)) => {
+                    tmp = tmp.child(self.confirm_delete(cx, strings, node))
+                }
+                Some(Mode::Trash) => tmp = tmp.child(self.trash(cx, strings)),
+                _ => {}
+            }
             tmp.finish()
         });
 
-        // Text Box
-        if let Some((text_box, page)) = &self.text_box {
+        // Tutorial, or else Text Box
+        if let Some(step) = self.tutorial() {
+            col0 = col0.child({
+                let mut tmp = div(cx.bump)
+                    .attributes([attr("class", "text-box tutorial")])
+                    .child(text(
+                        bumpalo::format!(in cx.bump,
+                            "({}/{}) {}",
+                            self.tutorial_step + 1,
+                            self.tutorial.len(),
+                            step.message.get(strings)
+                        )
+                        .into_bump_str(),
+                    ));
+                if step.advance == tutorial::Action::Next {
+                    tmp = tmp.child(text(" ")).child(
+                        a(cx.bump)
+                            .attributes([attr("class", "hoverable")])
+                            .listeners([Model::listener(cx.bump, "click", |_| {
+                                crate::Msg::Level(Msg::TutorialNext)
+                            })])
+                            .children([text(strings.get_in(cx.bump, Text::TutorialNext))])
+                            .finish(),
+                    );
+                }
+                tmp.finish()
+            });
+        } else if let Some((text_box, page)) = &self.text_box {
             col0 = col0.child({
                 let mut tmp = div(cx.bump)
                     .attributes([attr("class", "text-box")])
                     .child(text(
-                        bumpalo::collections::String::from_str_in(text_box, cx.bump)
+                        bumpalo::collections::String::from_str_in(text_box.get(strings), cx.bump)
                             .into_bump_str(),
                     ));
 
@@ -209,10 +441,9 @@ impl State {
                             a(cx.bump)
                                 .attributes([attr(
                                     "href",
-                                    bumpalo::format!(in cx.bump, "#{}", <&str>::from(*page))
-                                        .into_bump_str(),
+                                    bumpalo::format!(in cx.bump, "#{}", page).into_bump_str(),
                                 )])
-                                .children([text("More info")])
+                                .children([text(strings.get_in(cx.bump, Text::MoreInfo))])
                                 .finish(),
                         )
                         .child(text(")"));
@@ -222,13 +453,30 @@ impl State {
             });
         }
 
+        // Result of the counterexample search
+        if let Some(counterexample) = &self.counterexample {
+            col0 = col0.child(
+                div(cx.bump)
+                    .attributes([attr("class", "text-box")])
+                    .children([text(strings.get_in(
+                        cx.bump,
+                        if counterexample.is_some() {
+                            Text::CounterexampleFound
+                        } else {
+                            Text::NoCounterexample
+                        },
+                    ))])
+                    .finish(),
+            );
+        }
+
         // Case Tree
         if self.unlocks >= Unlocks::CASES {
-            col1 = col1.child(self.case_tree.render(
-                cx,
-                matches!(self.mode, Some(Mode::SelectUndo { .. })),
-                self.axiom,
-            ));
+            col1 = col1.child(self.case_tree_layer.render(cx, || CaseTreeView {
+                case_tree: Some(self.case_tree.clone()),
+                undo_buttons: matches!(self.mode, Some(Mode::SelectUndo { .. })),
+                axiom: self.axiom,
+            }));
 
             if matches!(self.mode, Some(Mode::SelectUndo { .. })) {
                 col1 = col1.child(
@@ -245,7 +493,7 @@ impl State {
                                 crate::Msg::Level(Msg::Cancel)
                             }),
                         ])
-                        .children([text("Cancel undo.")])
+                        .children([text(strings.get_in(cx.bump, Text::CancelUndo))])
                         .finish(),
                 )
             } else {
@@ -256,7 +504,7 @@ impl State {
                         .listeners([Model::listener(cx.bump, "click", move |_| {
                             crate::Msg::Level(Msg::RevertPreview(current))
                         })])
-                        .children([text("Undo")])
+                        .children([text(strings.get_in(cx.bump, Text::Undo))])
                         .finish(),
                 );
             }
@@ -270,20 +518,52 @@ impl State {
         );
 
         // Next Level
-        if self.axiom || self.case_tree.all_complete() {
+        if let (true, Some(current_level)) =
+            (self.axiom || self.case_tree.all_complete(), current_level)
+        {
             #[rustfmt::skip]
             let (listener, s) = if let Some(next_level) = next_level {(
                 Model::listener(cx.bump, "click", move |_| crate::Msg::GotoLevel(next_level)),
-                "Next Level!",
+                Text::NextLevel,
             )} else {(
                 Model::listener(cx.bump, "click", move |_| crate::Msg::GotoMap { recenter: true }),
-                "Select a Level!",
+                Text::SelectALevel,
             )};
             col1 = col1.child(
                 div(cx.bump)
                     .attributes([attr("class", "button green")])
                     .listeners([listener])
-                    .children([text(if self.axiom { "Continue." } else { s })])
+                    .children([text(
+                        strings.get_in(cx.bump, if self.axiom { Text::Continue } else { s }),
+                    )])
+                    .finish(),
+            );
+
+            // Axiom Audit
+            col1 = col1.child(
+                div(cx.bump)
+                    .attributes([attr("class", "button cyan")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::ShowAudit(Some(current_level))
+                    })])
+                    .children([text(strings.get_in(cx.bump, Text::AxiomsUsed))])
+                    .finish(),
+            );
+        } else if self.case_tree.all_complete() {
+            col1 = col1.child(
+                div(cx.bump)
+                    .attributes([attr("class", "button green")])
+                    .listeners([Model::listener(cx.bump, "click", move |_| {
+                        crate::Msg::CloseSandbox
+                    })])
+                    .children([text(strings.get_in(
+                        cx.bump,
+                        if self.playtest {
+                            Text::BackToTheEditor
+                        } else {
+                            Text::BackToTheBook
+                        },
+                    ))])
                     .finish(),
             );
         }
@@ -293,17 +573,35 @@ impl State {
             col1 = col1.child(
                 div(cx.bump)
                     .attributes([attr("class", "button red")])
-                    .listeners([Model::listener(cx.bump, "click", move |_| {
-                        crate::Msg::GotoLevel(current_level)
-                    })])
-                    .children([text("Reset")])
+                    .listeners([Model::listener(
+                        cx.bump,
+                        "click",
+                        move |_| match current_level {
+                            Some(current_level) => crate::Msg::GotoLevel(current_level),
+                            None => crate::Msg::ResetSandbox,
+                        },
+                    )])
+                    .children([text(strings.get_in(cx.bump, Text::Reset))])
                     .finish(),
             );
         }
 
         if self.interactable() {
+            // Find Counterexample, for statements that might not be provable.
+            if current_level.is_none() {
+                col1 = col1.child(
+                    div(cx.bump)
+                        .attributes([attr("class", "button cyan")])
+                        .listeners([Model::listener(cx.bump, "click", move |_| {
+                            crate::Msg::Level(Msg::FindCounterexample)
+                        })])
+                        .children([text(strings.get_in(cx.bump, Text::FindCounterexample))])
+                        .finish(),
+                );
+            }
+
             // Apply Theorem
-            if self.unlocks >= Unlocks::THEOREM_APPLICATION {
+            if self.unlocks >= Unlocks::THEOREM_APPLICATION && current_level.is_some() {
                 if matches!(
                     self.mode,
                     Some(Mode::ChooseTheoremLocation { .. } | Mode::AssignTheoremVars { .. })
@@ -314,17 +612,24 @@ impl State {
                             .listeners([Model::listener(cx.bump, "click", move |_| {
                                 crate::Msg::Level(Msg::Cancel)
                             })])
-                            .children([text("Cancel Application")])
+                            .children([text(strings.get_in(cx.bump, Text::CancelApplication))])
                             .finish(),
                     );
                 } else {
                     col1 = col1.child(
                         div(cx.bump)
-                            .attributes([attr("class", "button yellow")])
+                            .attributes([attr(
+                                "class",
+                                if self.tutorial_action() == Some(tutorial::Action::SelectTheorem) {
+                                    "button yellow tutorial-target"
+                                } else {
+                                    "button yellow"
+                                },
+                            )])
                             .listeners([Model::listener(cx.bump, "click", move |_| {
                                 crate::Msg::SelectTheorem
                             })])
-                            .children([text("Apply Theorem")])
+                            .children([text(strings.get_in(cx.bump, Text::ApplyTheorem))])
                             .finish(),
                     );
                 }
@@ -332,10 +637,25 @@ impl State {
         }
 
         // World Map and Book
-        col1 = col1.child(
+        col1 = col1.child(if current_level.is_none() {
             div(cx.bump)
+                .attributes([attr("class", "button blue")])
+                .listeners([Model::listener(cx.bump, "click", move |_| {
+                    crate::Msg::CloseSandbox
+                })])
+                .children([text(strings.get_in(
+                    cx.bump,
+                    if self.playtest {
+                        Text::BackToEditor
+                    } else {
+                        Text::BackToBook
+                    },
+                ))])
+                .finish()
+        } else {
+            let mut tmp = div(cx.bump)
                 .attr("style", "display: flex; flex-direction: row;")
-                .children([
+                .child(
                     div(cx.bump)
                         .attributes([
                             attr("id", "return-to-map"),
@@ -345,18 +665,25 @@ impl State {
                         .listeners([Model::listener(cx.bump, "click", move |_| {
                             crate::Msg::GotoMap { recenter: false }
                         })])
-                        .children([text("Return to Map")])
+                        .children([text(strings.get_in(cx.bump, Text::ReturnToMap))])
                         .finish(),
+                );
+            if let Some(contents) = &self.contents {
+                tmp = tmp.child(
                     a(cx.bump)
                         .attributes([
-                            attr("href", "#TableOfContents"),
+                            attr(
+                                "href",
+                                bumpalo::format!(in cx.bump, "#{}", contents).into_bump_str(),
+                            ),
                             attr("class", "button cyan"),
                         ])
                         .children([text("📖")])
                         .finish(),
-                ])
-                .finish(),
-        );
+                );
+            }
+            tmp.finish()
+        });
 
         [col0.finish(), col1.finish()]
     }
@@ -0,0 +1,53 @@
+//! Scripted tutorials, which walk the player through a level one step at a time.
+//!
+//! Nodes are referred to by their index in the level's `nodes`.
+//! Nodes created during play, such as by applying a theorem, can't be referred to.
+
+/// One step of a tutorial.
+#[derive(Clone)]
+pub struct Step {
+    pub message: crate::locale::Translated,
+    /// Nodes to draw attention to. If this or `wires` is nonempty, everything else is dimmed.
+    pub nodes: Vec<usize>,
+    /// Wires to draw attention to, each identified by a node it emerges from.
+    pub wires: Vec<usize>,
+    /// What the player has to do to move on to the next step.
+    pub advance: Action,
+}
+
+impl Step {
+    pub fn highlights(&self) -> bool {
+        !self.nodes.is_empty() || !self.wires.is_empty()
+    }
+
+    /// The nodes this step refers to, so they can be checked against the level.
+    pub fn referenced_nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        let advance = match self.advance {
+            Action::Click(n) | Action::ClickWire(n) => vec![n],
+            Action::Drag(n1, n2) => vec![n1, n2],
+            Action::SelectTheorem | Action::ApplyTheorem | Action::Next | Action::Complete => {
+                vec![]
+            }
+        };
+        self.nodes.iter().chain(&self.wires).copied().chain(advance)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Click the node, using its interaction.
+    Click(usize),
+    /// Click the wire emerging from the node, proving it as a lemma.
+    ClickWire(usize),
+    /// Drag one node onto the other, in either direction, connecting them.
+    Drag(usize, usize),
+    /// Open the theorem selection screen.
+    SelectTheorem,
+    /// Finish applying a theorem.
+    ApplyTheorem,
+    /// Press the "Next" button beside the message.
+    Next,
+    /// Complete the level.
+    Complete,
+}
@@ -1,20 +1,84 @@
 #![warn(clippy::todo)]
 #![allow(clippy::new_without_default)]
 
+use architecture::Architecture;
 use game_data::{GameData, SaveData};
+use locale::Text;
+use route::Route;
 use wasm_bindgen::{prelude::Closure, JsCast};
 
 mod architecture;
 mod book;
+mod editor;
 mod file;
 mod game_data;
 mod level;
+mod locale;
 mod render;
+mod route;
+mod theorem_select;
 mod world_map;
 
 #[wasm_bindgen::prelude::wasm_bindgen]
 pub fn run() {
     architecture::main::<Model>(|document, send_msg| {
+        let window = web_sys::window().unwrap();
+        for event in ["popstate", "hashchange"] {
+            let send_msg = send_msg.clone();
+            window
+                .add_event_listener_with_callback(
+                    event,
+                    Closure::wrap(Box::new(move |_: web_sys::Event| {
+                        send_msg.send_blocking(Msg::UrlChanged).unwrap();
+                    }) as Box<dyn Fn(web_sys::Event)>)
+                    .into_js_value()
+                    .unchecked_ref(),
+                )
+                .unwrap();
+        }
+
+        // A deep link into the game needs the levels before it can be followed.
+        if route::is_route(&window.location().hash().unwrap()) {
+            file::fetch(
+                file::pack_path(),
+                Msg::LoadedLevels,
+                Msg::LoadingLevelsFailed,
+                send_msg.clone(),
+            );
+        }
+
+        // Buttons in the book that open a sandbox level. The book isn't part of the virtual DOM,
+        // so they can't have listeners of their own.
+        let send_book_msg = send_msg.clone();
+        document
+            .get_element_by_id("book")
+            .unwrap()
+            .add_event_listener_with_callback(
+                "click",
+                Closure::wrap(Box::new(move |e: web_sys::Event| {
+                    let button = e
+                        .target()
+                        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                        .and_then(|target| target.closest("[data-book-level]").ok()?);
+                    let Some(button) = button else { return };
+                    let (Some(page), Some(level)) = (
+                        button.closest(".book").ok().flatten(),
+                        button.get_attribute("data-book-level"),
+                    ) else {
+                        return;
+                    };
+                    send_book_msg
+                        .send_blocking(Msg::OpenSandbox {
+                            page: page.id(),
+                            level,
+                        })
+                        .unwrap();
+                }) as Box<dyn Fn(web_sys::Event)>)
+                .into_js_value()
+                .unchecked_ref(),
+            )
+            .unwrap();
+
         let send_msg = send_msg.clone();
         document
             .body()
@@ -49,6 +113,10 @@ struct Model {
     // dynamic
     game_state: GameState,
     global_state: GlobalState,
+    /// The level whose axiom audit is being displayed, if any.
+    audit: Option<usize>,
+    /// A level from the book being tried out, along with the page and name it came from.
+    sandbox: Option<(String, String, Box<level::State>)>,
 }
 
 pub struct GlobalState {
@@ -64,9 +132,12 @@ enum GameState {
         level: usize,
         next_level: Option<usize>,
         level_state: Box<level::State>,
-        theorem_select: Option<(world_map::State, Option<usize>)>,
+        theorem_select: Option<theorem_select::State>,
         theorem_select_panzoom: render::PanZoom,
     },
+    Editor {
+        editor_state: Box<editor::State>,
+    },
 }
 
 impl GameState {
@@ -113,14 +184,32 @@ enum Msg {
 
     // Messages related to selecting theorems from the world map while in a level.
     SelectTheorem,
+    TheoremSelect(theorem_select::Msg),
     PreviewTheorem(usize),
     SelectedTheorem(Option<usize>),
 
+    ShowAudit(Option<usize>),
+    ToggleConstructive,
+    ToggleWireStyle,
+    NextLanguage,
+
+    // Messages related to levels embedded in the book.
+    // Resetting and closing a sandbox also apply to a play-test in the editor.
+    OpenSandbox { page: String, level: String },
+    ResetSandbox,
+    CloseSandbox,
+
+    OpenEditor,
+    Editor(editor::Msg),
+
     LoadedSave(String),
     LoadingSaveFailed(),
     LoadedLevels(String),
+    LoadingLevelsFailed(),
 
     KeyPress { key: String, repeat: bool },
+    // The browser changed the location, e.g. through the back button.
+    UrlChanged,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -152,6 +241,8 @@ impl architecture::Architecture for Model {
             global_state: GlobalState {
                 map_panzoom: render::PanZoom::center([0.; 2], 10.),
             },
+            audit: None,
+            sandbox: None,
         }
     }
 
@@ -159,27 +250,26 @@ impl architecture::Architecture for Model {
         match msg {
             Msg::KeyPress { key, repeat } => {
                 let location = web_sys::window().unwrap().location();
+                let hash = location.hash().unwrap();
 
-                if let Some(page) = location.hash().unwrap().as_str().strip_prefix('#') {
+                if self.sandbox.is_some() {
+                    if let (Some(msg), false) = (self.key_binding(&key), repeat) {
+                        self.update(msg, rerender)
+                    }
+                } else if let Some(page) =
+                    hash.strip_prefix('#').filter(|_| !route::is_route(&hash))
+                {
                     if key == "Escape" && !repeat {
                         location.set_hash("").unwrap();
-                    } else if let Ok(page) = book::BookPage::try_from(page) {
-                        match (key.as_str(), repeat) {
-                            ("ArrowLeft", _) => {
-                                if let Some(page) = page.prev() {
-                                    location
-                                        .set_hash(&format!("#{}", <&str>::from(page)))
-                                        .unwrap();
-                                }
-                            }
-                            ("ArrowRight", _) => {
-                                if let Some(page) = page.next() {
-                                    location
-                                        .set_hash(&format!("#{}", <&str>::from(page)))
-                                        .unwrap();
-                                }
-                            }
-                            _ => {}
+                    } else if let Some(page) = self.game_data.book().page(page) {
+                        let book = self.game_data.book();
+                        let page = match key.as_str() {
+                            "ArrowLeft" => book.prev(page),
+                            "ArrowRight" => book.next(page),
+                            _ => None,
+                        };
+                        if let Some(page) = page {
+                            location.set_hash(&format!("#{}", book.id(page))).unwrap();
                         }
                     }
                 } else if let (Some(msg), false) = (self.key_binding(&key), repeat) {
@@ -187,13 +277,24 @@ impl architecture::Architecture for Model {
                 }
             }
             Msg::Level(msg) => {
-                if let GameState::Level {
+                if let Some((_, _, sandbox)) = &mut self.sandbox {
+                    sandbox.update(msg, rerender);
+                } else if let GameState::Editor { editor_state } = &mut self.game_state {
+                    if let Some(playtest) = &mut editor_state.playtest {
+                        playtest.update(msg, rerender);
+                    }
+                } else if let GameState::Level {
                     level_state, level, ..
                 } = &mut self.game_state
                 {
                     level_state.update(msg, rerender);
                     if level_state.complete() {
-                        if self.save_data.mark_completed(*level) {
+                        let changed = self.save_data.mark_completed(*level)
+                            | self.save_data.record_stats(*level, level_state.stats())
+                            | self
+                                .save_data
+                                .record_proof(*level, level_state.theorems_used());
+                        if changed {
                             web_sys::window()
                                 .unwrap()
                                 .set_onbeforeunload(Some(&self.save_listener));
@@ -208,11 +309,13 @@ impl architecture::Architecture for Model {
                     map_state.update(msg, &mut self.global_state.map_panzoom, rerender);
                 }
                 GameState::Level {
-                    theorem_select: Some((map_state, _)),
+                    theorem_select: Some(theorem_select),
                     theorem_select_panzoom,
                     ..
                 } => {
-                    map_state.update(msg, theorem_select_panzoom, rerender);
+                    theorem_select
+                        .map_state
+                        .update(msg, theorem_select_panzoom, rerender);
                 }
                 _ => {}
             },
@@ -230,6 +333,10 @@ impl architecture::Architecture for Model {
                 *rerender = true;
             }
             Msg::GotoMap { recenter } => match self.game_state {
+                GameState::Editor { .. } => {
+                    self.game_state = GameState::map();
+                    *rerender = true;
+                }
                 GameState::Level {
                     level: level_num, ..
                 } => {
@@ -245,16 +352,33 @@ impl architecture::Architecture for Model {
                 GameState::WorldMap { .. } | GameState::Menu => {}
             },
             Msg::SelectTheorem => {
-                if let GameState::Level { theorem_select, .. } = &mut self.game_state {
+                if let GameState::Level {
+                    theorem_select,
+                    level_state,
+                    ..
+                } = &mut self.game_state
+                {
                     if theorem_select.is_none() {
-                        *theorem_select = Some((world_map::State::new(), None));
+                        *theorem_select =
+                            Some(theorem_select::State::new(&self.game_data, &self.save_data));
+                        level_state.opened_theorem_select();
                         *rerender = true;
                     }
                 }
             }
+            Msg::TheoremSelect(msg) => {
+                if let GameState::Level {
+                    theorem_select: Some(theorem_select),
+                    level_state,
+                    ..
+                } = &mut self.game_state
+                {
+                    theorem_select.update(msg, &self.game_data, level_state, rerender);
+                }
+            }
             Msg::PreviewTheorem(level) => {
                 if let GameState::Level {
-                    theorem_select: Some((_, preview)),
+                    theorem_select: Some(theorem_select::State { preview, .. }),
                     ..
                 } = &mut self.game_state
                 {
@@ -272,19 +396,120 @@ impl architecture::Architecture for Model {
                 } = &mut self.game_state
                 {
                     *theorem_select = None;
-                    if let Some(level) = level {
+                    if let Some(level) = level
+                        .filter(|&level| self.save_data.theorem_available(&self.game_data, level))
+                    {
                         level_state.update(
-                            level::Msg::SelectedTheorem(self.game_data.level(level).spec.clone()),
+                            level::Msg::SelectedTheorem(
+                                level,
+                                self.game_data.level(level).spec.clone(),
+                            ),
                             rerender,
                         );
                     }
                     *rerender = true;
                 }
             }
-            Msg::LoadedSave(save_file) => match SaveData::load(&self.game_data, &save_file) {
-                Ok(save_data) => {
-                    self.save_data = save_data;
-                    web_sys::window().unwrap().set_onbeforeunload(None);
+            Msg::ShowAudit(level) => {
+                self.audit = level;
+                *rerender = true;
+            }
+            Msg::OpenSandbox { page, level } => {
+                if let Some(sandbox) = self.game_data.book().sandbox(&page, &level) {
+                    self.sandbox = Some((page, level, Box::new(sandbox.load())));
+                    *rerender = true;
+                }
+            }
+            Msg::ResetSandbox => {
+                if let Some((page, level, _)) = self.sandbox.take() {
+                    self.update(Msg::OpenSandbox { page, level }, rerender);
+                } else if let GameState::Editor { editor_state } = &mut self.game_state {
+                    editor_state.update(editor::Msg::Playtest, rerender);
+                }
+            }
+            Msg::CloseSandbox => {
+                if self.sandbox.is_some() {
+                    self.sandbox = None;
+                } else if let GameState::Editor { editor_state } = &mut self.game_state {
+                    editor_state.playtest = None;
+                }
+                *rerender = true;
+            }
+            Msg::OpenEditor => {
+                if !matches!(self.game_state, GameState::Editor { .. }) {
+                    self.game_state = GameState::Editor {
+                        editor_state: Box::new(editor::State::new(
+                            self.game_data.definitions().clone(),
+                        )),
+                    };
+                    *rerender = true;
+                }
+            }
+            Msg::Editor(msg) => {
+                if let GameState::Editor { editor_state } = &mut self.game_state {
+                    editor_state.update(msg, rerender);
+                }
+            }
+            Msg::ToggleConstructive => {
+                self.save_data.toggle_constructive();
+                web_sys::window()
+                    .unwrap()
+                    .set_onbeforeunload(Some(&self.save_listener));
+                *rerender = true;
+            }
+            Msg::ToggleWireStyle => {
+                self.save_data.toggle_wire_style();
+                web_sys::window()
+                    .unwrap()
+                    .set_onbeforeunload(Some(&self.save_listener));
+                *rerender = true;
+            }
+            Msg::NextLanguage => {
+                self.save_data.next_language(&self.game_data);
+                self.game_data.book().install(self.strings());
+                web_sys::window()
+                    .unwrap()
+                    .set_onbeforeunload(Some(&self.save_listener));
+                *rerender = true;
+            }
+            Msg::LoadedSave(save_file) => match self.save_data.load(&self.game_data, &save_file) {
+                Ok(warnings) => {
+                    self.game_data.book().install(self.strings());
+                    // Without progress in this pack, the save file didn't replace the unsaved progress.
+                    if !warnings
+                        .iter()
+                        .any(|warning| matches!(warning, game_data::SaveWarning::OtherPacks(_)))
+                    {
+                        web_sys::window().unwrap().set_onbeforeunload(None);
+                    }
+                    if !warnings.is_empty() {
+                        let strings = self.strings();
+                        let message = warnings
+                            .into_iter()
+                            .map(|warning| match warning {
+                                game_data::SaveWarning::OtherPacks(packs) => {
+                                    strings.format(Text::SaveOtherPacks, &packs.join(", "))
+                                }
+                                game_data::SaveWarning::Untagged => {
+                                    strings.get(Text::SaveUntagged).to_owned()
+                                }
+                                game_data::SaveWarning::Version(version) => {
+                                    strings.format(Text::SaveVersion, &version.to_string())
+                                }
+                                game_data::SaveWarning::UnknownLevels(levels) => {
+                                    strings.format(Text::SaveUnknownLevels, &levels.join(", "))
+                                }
+                                game_data::SaveWarning::ChangedLevels(levels) => {
+                                    strings.format(Text::SaveChangedLevels, &levels.join(", "))
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n\n");
+                        web_sys::window()
+                            .unwrap()
+                            .alert_with_message(&message)
+                            .unwrap();
+                    }
                     *rerender = true;
                 }
                 Err(err) => {
@@ -294,16 +519,58 @@ impl architecture::Architecture for Model {
             Msg::LoadingSaveFailed() => {
                 web_sys::console::warn_1(&"Failed to load save file.".into());
             }
+            Msg::LoadingLevelsFailed() => {
+                web_sys::window()
+                    .unwrap()
+                    .alert_with_message(self.strings().get(Text::LevelPackUnavailable))
+                    .unwrap();
+            }
             Msg::LoadedLevels(json) => {
-                self.game_data = serde_json::from_str(&json).unwrap();
+                // A pack from a file can be anything, so a bad one leaves the menu up instead of panicking.
+                match serde_json::from_str(&json) {
+                    Ok(game_data) => self.game_data = game_data,
+                    Err(err) => {
+                        web_sys::window()
+                            .unwrap()
+                            .alert_with_message(
+                                &self
+                                    .strings()
+                                    .format(Text::LevelPackInvalid, &err.to_string()),
+                            )
+                            .unwrap();
+                        return;
+                    }
+                }
+                for warning in self.game_data.check_levels() {
+                    web_sys::console::warn_1(&warning.into());
+                }
                 self.save_data = SaveData::new(&self.game_data);
+                self.game_data.book().install(self.strings());
                 self.game_state = GameState::map();
                 self.global_state = GlobalState {
                     map_panzoom: render::PanZoom::center([0.; 2], 10.),
                 };
                 *rerender = true;
+
+                if let Some(route) = Route::parse(&current_hash()) {
+                    self.navigate(route, rerender);
+                }
+            }
+            Msg::UrlChanged => {
+                if let Some((page, _, _)) = &self.sandbox {
+                    if current_hash() != format!("#{page}") {
+                        self.sandbox = None;
+                        *rerender = true;
+                    }
+                }
+                if let Some(route) = Route::parse(&current_hash()) {
+                    self.navigate(route, rerender);
+                }
+                self.sync_url(true);
             }
         }
+
+        self.sync_url(false);
     }
 
     fn view<'a>(&self, cx: &mut dodrio::RenderContext<'a>) -> dodrio::Node<'a> {
@@ -323,11 +590,37 @@ impl architecture::Architecture for Model {
                 theorem_select: None,
                 theorem_select_panzoom: _,
             } => {
-                for child in level_state.render(cx, *level, *next_level) {
+                for child in level_state.render(
+                    cx,
+                    self.strings(),
+                    self.save_data.wire_style(),
+                    Some(*level),
+                    *next_level,
+                ) {
+                    builder = builder.child(child);
+                }
+            }
+            GameState::Editor { editor_state } => {
+                let children = match &editor_state.playtest {
+                    Some(playtest) => {
+                        playtest.render(cx, self.strings(), self.save_data.wire_style(), None, None)
+                    }
+                    None => editor_state.render(cx, self.strings(), &self.game_data),
+                };
+                for child in children {
                     builder = builder.child(child);
                 }
             }
             GameState::WorldMap { map_state } => {
+                let [save, load, load_input] = save_load_buttons(cx.bump, self.strings());
+                let audit = map_state.audit_button(cx, self.strings());
+                let constructive =
+                    world_map::State::constructive_button(cx, self.strings(), &self.save_data);
+                let wire_style =
+                    world_map::State::wire_style_button(cx, self.strings(), &self.save_data);
+                let language =
+                    world_map::State::language_button(cx, &self.game_data, &self.save_data);
+                let editor = world_map::State::editor_button(cx, self.strings());
                 builder = builder
                     .child(
                         div(cx.bump)
@@ -341,37 +634,48 @@ impl architecture::Architecture for Model {
                             )])
                             .finish(),
                     )
-                    .child(
-                        div(cx.bump)
-                            .attributes([attr("class", "col narrow")])
-                            .children(save_load_buttons(cx.bump))
-                            .finish(),
-                    )
+                    .child({
+                        let mut col1 = div(cx.bump).attributes([attr("class", "col narrow")]);
+                        for child in [save, load, load_input, audit, constructive, wire_style]
+                            .into_iter()
+                            .chain(language)
+                            .chain([editor])
+                        {
+                            col1 = col1.child(child);
+                        }
+                        col1.finish()
+                    })
             }
             GameState::Level {
-                theorem_select: Some((map_state, preview)),
+                theorem_select: Some(theorem_select),
                 theorem_select_panzoom,
                 ..
             } => {
+                let preview = theorem_select.preview;
                 let col0 = div(cx.bump)
                     .attributes([attr("class", "col wide")])
                     .children([
-                        map_state.render(
+                        theorem_select.map_state.render(
                             cx,
                             &self.game_data,
                             theorem_select_panzoom,
                             &self.save_data,
-                            Some(*preview),
+                            Some(preview),
                         ),
                         div(cx.bump)
                             .attributes([attr("class", "background disabled text-box")])
-                            .children([text("Select a theorem to apply.")])
+                            .children([text(
+                                self.strings().get_in(cx.bump, Text::SelectTheoremToApply),
+                            )])
                             .finish(),
                     ])
                     .finish();
                 let mut col1 = div(cx.bump).attributes([attr("class", "col wide")]);
+                for child in theorem_select.render_list(cx, self.strings(), &self.game_data) {
+                    col1 = col1.child(child);
+                }
                 if let Some(preview) = preview {
-                    let preview = self.game_data.level(*preview);
+                    let preview = self.game_data.level(preview);
                     let mut svg = svg(cx.bump).attributes([
                         attr("class", "background disabled"),
                         attr("preserveAspectRatio", "xMidYMid meet"),
@@ -389,7 +693,9 @@ impl architecture::Architecture for Model {
                         .listeners([Model::listener(cx.bump, "click", move |_| {
                             Msg::SelectedTheorem(None)
                         })])
-                        .children([text("Cancel Application")])
+                        .children([text(
+                            self.strings().get_in(cx.bump, Text::CancelApplication),
+                        )])
                         .finish(),
                 );
                 builder = builder.child(col0).child(col1.finish())
@@ -403,11 +709,36 @@ impl architecture::Architecture for Model {
                                 .attributes([attr("class", "button green")])
                                 .listeners([file::fetch_listener(
                                     cx.bump,
-                                    "levels.json",
+                                    file::pack_path(),
                                     Msg::LoadedLevels,
-                                    || panic!("Failed to load levels."),
+                                    Msg::LoadingLevelsFailed,
+                                )])
+                                .children([text(self.strings().get_in(cx.bump, Text::Start))])
+                                .finish(),
+                            div(cx.bump)
+                                .attributes([attr("class", "button blue")])
+                                .listeners([on(cx.bump, "click", |_, _, _| {
+                                    let _ = || -> Option<()> {
+                                        web_sys::window()?
+                                            .document()?
+                                            .get_element_by_id("load-pack-input")?
+                                            .dyn_into::<web_sys::HtmlElement>()
+                                            .ok()?
+                                            .click();
+                                        Some(())
+                                    }();
+                                })])
+                                .children([text(
+                                    self.strings().get_in(cx.bump, Text::LoadLevelPack),
+                                )])
+                                .finish(),
+                            input(cx.bump)
+                                .attributes([attr("id", "load-pack-input"), attr("type", "file")])
+                                .listeners([file::load_listener(
+                                    cx.bump,
+                                    Msg::LoadedLevels,
+                                    Msg::LoadingLevelsFailed,
                                 )])
-                                .children([text("Start!")])
                                 .finish(),
                             div(cx.bump)
                                 .attributes([attr("style", "flex: 1;")])
@@ -425,15 +756,261 @@ impl architecture::Architecture for Model {
             }
         };
 
+        if let Some(level) = self.audit {
+            builder = builder.child(self.audit_report(cx, level));
+        }
+
+        if let Some((_, _, sandbox)) = &self.sandbox {
+            builder = builder.child(
+                div(cx.bump)
+                    .attributes([attr("class", "sandbox background")])
+                    .children(sandbox.render(
+                        cx,
+                        self.strings(),
+                        self.save_data.wire_style(),
+                        None,
+                        None,
+                    ))
+                    .finish(),
+            );
+        }
+
         builder.finish()
     }
 }
 
 impl Model {
+    /// The game's text, in the player's chosen language.
+    fn strings(&self) -> locale::Strings<'_> {
+        self.game_data.strings(self.save_data.language())
+    }
+
+    /// The route describing the current game state, if any.
+    fn route(&self) -> Option<Route> {
+        match &self.game_state {
+            GameState::Menu => None,
+            GameState::WorldMap { .. } => Some(Route::Map),
+            GameState::Editor { .. } => Some(Route::Editor),
+            GameState::Level {
+                level,
+                theorem_select,
+                ..
+            } => {
+                let name = self.game_data.level(*level).name().to_owned();
+                Some(if theorem_select.is_some() {
+                    Route::SelectTheorem(name)
+                } else {
+                    Route::Level(name)
+                })
+            }
+        }
+    }
+
+    /// Bring the location hash in line with the game state.
+    /// Book pages are left alone, since they are displayed on top of the game.
+    fn sync_url(&self, replace: bool) {
+        let Some(route) = self.route() else { return };
+        let hash = current_hash();
+        let route = route.to_hash();
+        if hash == route || (!hash.is_empty() && !route::is_route(&hash)) {
+            return;
+        }
+
+        let history = web_sys::window().unwrap().history().unwrap();
+        if replace || hash.is_empty() {
+            history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&route))
+        } else {
+            history.push_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&route))
+        }
+        .unwrap();
+    }
+
+    /// Bring the game state in line with a route.
+    fn navigate(&mut self, route: Route, rerender: &mut bool) {
+        if self.game_data.num_levels() == 0 || self.route().as_ref() == Some(&route) {
+            return;
+        }
+
+        let name = match &route {
+            Route::Map => {
+                if let GameState::Level { level, .. } = self.game_state {
+                    self.global_state.map_panzoom =
+                        render::PanZoom::center(self.game_data.level(level).map_position, 10.);
+                }
+                self.game_state = GameState::map();
+                *rerender = true;
+                return;
+            }
+            Route::Editor => {
+                self.update(Msg::OpenEditor, rerender);
+                return;
+            }
+            Route::Level(name) | Route::SelectTheorem(name) => name,
+        };
+
+        let Some(level) = self.game_data.level_by_name(name) else {
+            return;
+        };
+        match &mut self.game_state {
+            GameState::Level {
+                level: current,
+                theorem_select,
+                ..
+            } if *current == level => {
+                *theorem_select = None;
+                *rerender = true;
+            }
+            // A link is followed even into a locked level, since a fresh page hasn't loaded the save
+            // the level was unlocked in. Only reaching an axiom from the map grants it, though.
+            _ => {
+                let unlocked = self
+                    .game_data
+                    .level(level)
+                    .prereqs
+                    .iter()
+                    .all(|&prereq| self.save_data.completed(prereq));
+                if unlocked {
+                    self.update(Msg::GotoLevel(level), rerender);
+                } else {
+                    self.game_state = GameState::level(&self.game_data, level, &self.save_data);
+                    *rerender = true;
+                }
+            }
+        }
+
+        if let Route::SelectTheorem(_) = route {
+            self.update(Msg::SelectTheorem, rerender);
+        }
+    }
+
+    fn audit_report<'a>(
+        &self,
+        cx: &mut dodrio::RenderContext<'a>,
+        level: usize,
+    ) -> dodrio::Node<'a> {
+        use dodrio::builder::*;
+        use dodrio::bumpalo::{collections::String, format};
+        let strings = self.strings();
+
+        let game_data::Audit {
+            axioms,
+            unrecorded,
+            classical,
+        } = self.save_data.audit(&self.game_data, level);
+
+        let names = |levels: std::collections::BTreeSet<usize>| {
+            let mut names = String::new_in(cx.bump);
+            for (i, level) in levels.into_iter().enumerate() {
+                if i > 0 {
+                    names.push_str(", ");
+                }
+                names.push_str(self.game_data.level(level).name());
+            }
+            names
+        };
+
+        let mut content = div(cx.bump)
+            .attributes([attr("class", "book-content")])
+            .child(
+                div(cx.bump)
+                    .attributes([attr("class", "book-close hoverable")])
+                    .listeners([Model::listener(cx.bump, "click", |_| Msg::ShowAudit(None))])
+                    .children([text("❌")])
+                    .finish(),
+            )
+            .child(
+                h1(cx.bump)
+                    .children([text(
+                        String::from_str_in(
+                            &strings.format(Text::AxiomsUsedBy, self.game_data.level(level).name()),
+                            cx.bump,
+                        )
+                        .into_bump_str(),
+                    )])
+                    .finish(),
+            );
+
+        if axioms.is_empty() {
+            content = content.child(
+                p(cx.bump)
+                    .children([text(strings.get_in(cx.bump, Text::NoAxioms))])
+                    .finish(),
+            );
+        } else {
+            let mut list = ul(cx.bump);
+            for axiom in axioms {
+                let axiom = self.game_data.level(axiom);
+                list = list.child(
+                    li(cx.bump)
+                        .children([text(
+                            format!(in cx.bump, "{}: {}", axiom.name(), axiom.spec.statement())
+                                .into_bump_str(),
+                        )])
+                        .finish(),
+                );
+            }
+            content = content.child(list.finish());
+        }
+
+        if !classical.is_empty() {
+            content = content.child(
+                p(cx.bump)
+                    .children([text(
+                        String::from_str_in(
+                            &strings.format(Text::ClassicalThrough, &names(classical)),
+                            cx.bump,
+                        )
+                        .into_bump_str(),
+                    )])
+                    .finish(),
+            );
+        }
+
+        if !unrecorded.is_empty() {
+            content = content.child(
+                p(cx.bump)
+                    .children([text(
+                        String::from_str_in(
+                            &strings.format(Text::Unrecorded, &names(unrecorded)),
+                            cx.bump,
+                        )
+                        .into_bump_str(),
+                    )])
+                    .finish(),
+            );
+        }
+
+        div(cx.bump)
+            .attributes([attr("class", "audit background")])
+            .children([content.finish()])
+            .finish()
+    }
+
     fn key_binding(&self, key: &str) -> Option<Msg> {
+        if let Some((_, _, sandbox)) = &self.sandbox {
+            return match key {
+                "Escape" if sandbox.in_mode() => Some(Msg::Level(level::Msg::Cancel)),
+                "Escape" => Some(Msg::CloseSandbox),
+                _ => None,
+            };
+        }
+        if self.audit.is_some() {
+            return match key {
+                "Escape" => Some(Msg::ShowAudit(None)),
+                _ => None,
+            };
+        }
         match &self.game_state {
             GameState::Menu => None,
             GameState::WorldMap { .. } => None,
+            GameState::Editor { editor_state } => match (key, &editor_state.playtest) {
+                ("Escape", Some(playtest)) if playtest.in_mode() => {
+                    Some(Msg::Level(level::Msg::Cancel))
+                }
+                ("Escape", Some(_)) => Some(Msg::CloseSandbox),
+                ("Escape", None) => Some(Msg::GotoMap { recenter: false }),
+                _ => None,
+            },
             GameState::Level {
                 theorem_select: Some(_),
                 ..
@@ -472,7 +1049,14 @@ impl Model {
     }
 }
 
-fn save_load_buttons(bump: &dodrio::bumpalo::Bump) -> [dodrio::Node; 3] {
+fn current_hash() -> String {
+    web_sys::window().unwrap().location().hash().unwrap()
+}
+
+fn save_load_buttons<'a>(
+    bump: &'a dodrio::bumpalo::Bump,
+    strings: locale::Strings,
+) -> [dodrio::Node<'a>; 3] {
     use dodrio::builder::*;
     [
         if web_sys::window().unwrap().onbeforeunload().is_none() {
@@ -481,7 +1065,7 @@ fn save_load_buttons(bump: &dodrio::bumpalo::Bump) -> [dodrio::Node; 3] {
                     attr("id", "save-game"),
                     attr("class", "button blue disabled"),
                 ])
-                .children([text("Save Game")])
+                .children([text(strings.get_in(bump, Text::SaveGame))])
                 .finish()
         } else {
             div(bump)
@@ -494,7 +1078,7 @@ fn save_load_buttons(bump: &dodrio::bumpalo::Bump) -> [dodrio::Node; 3] {
                     },
                     "savefile.json",
                 )])
-                .children([text("Save Game")])
+                .children([text(strings.get_in(bump, Text::SaveGame))])
                 .finish()
         },
         div(bump)
@@ -510,7 +1094,7 @@ fn save_load_buttons(bump: &dodrio::bumpalo::Bump) -> [dodrio::Node; 3] {
                     Some(())
                 }();
             })])
-            .children([text("Load Save")])
+            .children([text(strings.get_in(bump, Text::LoadSave))])
             .finish(),
         input(bump)
             .attributes([attr("id", "load-savegame-input"), attr("type", "file")])
@@ -0,0 +1,261 @@
+//! The game's own text, and its translations.
+//!
+//! English is built in. Level packs can declare other languages,
+//! translating both the text here and their own content.
+//! Anything left untranslated falls back to the default.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Text {
+    Start,
+    LoadLevelPack,
+    LevelPackUnavailable,
+    /// `{}` is replaced by what's wrong with the level pack.
+    LevelPackInvalid,
+    SaveGame,
+    LoadSave,
+    AuditAxioms,
+    StopAuditing,
+    ConstructiveModeOn,
+    ConstructiveModeOff,
+    /// `{}` is replaced by the name of the language.
+    Language,
+    WireStyleCurved,
+    WireStyleRouted,
+    /// `{}` is replaced by a list of level packs.
+    SaveOtherPacks,
+    SaveUntagged,
+    /// `{}` is replaced by the save's version of the level pack.
+    SaveVersion,
+    /// `{}` is replaced by a list of levels.
+    SaveUnknownLevels,
+    /// `{}` is replaced by a list of levels.
+    SaveChangedLevels,
+
+    ReturnToMap,
+    NextLevel,
+    SelectALevel,
+    Continue,
+    Reset,
+    Undo,
+    CancelUndo,
+    ApplyTheorem,
+    SelectTheoremToApply,
+    SearchTheorems,
+    MatchingGoalOn,
+    MatchingGoalOff,
+    NoTheoremsFound,
+    CancelApplication,
+    MoreInfo,
+    TutorialNext,
+    FindCounterexample,
+    CounterexampleFound,
+    NoCounterexample,
+
+    NodeHoldsGoal,
+    NodeHoldsFact,
+    Delete,
+    Keep,
+    DeletedNodes,
+    TrashEmpty,
+    Close,
+
+    AxiomsUsed,
+    /// `{}` is replaced by the name of the level.
+    AxiomsUsedBy,
+    NoAxioms,
+    /// `{}` is replaced by a list of levels.
+    ClassicalThrough,
+    /// `{}` is replaced by a list of levels.
+    Unrecorded,
+
+    BackToBook,
+    BackToTheBook,
+
+    LevelEditor,
+    ToolMove,
+    ToolConnect,
+    ToolHypothesis,
+    ToolConclusion,
+    ToolDelete,
+    HelpMove,
+    HelpConnect,
+    HelpHypothesis,
+    HelpConclusion,
+    HelpDelete,
+    VariableName,
+    AddTruthVariable,
+    AddNumberVariable,
+    StatementValid,
+    /// `{}` is replaced by truth values of the statement's atoms.
+    NotTautology,
+    Playtest,
+    ExportLevel,
+    BackToEditor,
+    BackToTheEditor,
+}
+
+impl Text {
+    fn english(self) -> &'static str {
+        match self {
+            Text::Start => "Start!",
+            Text::LoadLevelPack => "Load Level Pack…",
+            Text::LevelPackUnavailable => "The level pack couldn't be loaded.",
+            Text::LevelPackInvalid => "The level pack couldn't be read. {}",
+            Text::SaveGame => "Save Game",
+            Text::LoadSave => "Load Save",
+            Text::AuditAxioms => "Audit Axioms",
+            Text::StopAuditing => "Stop Auditing",
+            Text::ConstructiveModeOn => "Constructive Mode: On",
+            Text::ConstructiveModeOff => "Constructive Mode: Off",
+            Text::Language => "Language: {}",
+            Text::WireStyleCurved => "Wires: Curved",
+            Text::WireStyleRouted => "Wires: Routed",
+            Text::SaveOtherPacks => {
+                "This save has no progress in this level pack, so your progress here is kept. It has progress in: {}."
+            }
+            Text::SaveUntagged => {
+                "This save doesn't say which level pack it's from, so it was loaded as if it's from this one."
+            }
+            Text::SaveVersion => {
+                "This save is from version {} of the level pack, which has since changed."
+            }
+            Text::SaveUnknownLevels => {
+                "This save has progress in levels this pack doesn't have, which was dropped: {}."
+            }
+            Text::SaveChangedLevels => {
+                "These levels, or theorems their proofs applied, have changed since you completed them, so they need proving again: {}."
+            }
+
+            Text::ReturnToMap => "Return to Map",
+            Text::NextLevel => "Next Level!",
+            Text::SelectALevel => "Select a Level!",
+            Text::Continue => "Continue.",
+            Text::Reset => "Reset",
+            Text::Undo => "Undo",
+            Text::CancelUndo => "Cancel undo.",
+            Text::ApplyTheorem => "Apply Theorem",
+            Text::SelectTheoremToApply => "Select a theorem to apply.",
+            Text::SearchTheorems => "Search by name or symbol…",
+            Text::MatchingGoalOn => "Only Matching Goal: On",
+            Text::MatchingGoalOff => "Only Matching Goal: Off",
+            Text::NoTheoremsFound => "No theorems found.",
+            Text::CancelApplication => "Cancel Application",
+            Text::MoreInfo => "More info",
+            Text::TutorialNext => "Next ►",
+            Text::FindCounterexample => "Find Counterexample",
+            Text::CounterexampleFound => {
+                "With the values shown, every known fact holds but the goal doesn't, \
+                 so the goal can't be proven from these facts alone."
+            }
+            Text::NoCounterexample => "No counterexample was found among small values.",
+
+            Text::NodeHoldsGoal => "This node holds the goal. Delete it anyway?",
+            Text::NodeHoldsFact => "This node holds a known fact. Delete it anyway?",
+            Text::Delete => "Delete",
+            Text::Keep => "Keep",
+            Text::DeletedNodes => "Deleted nodes. Click one to restore it.",
+            Text::TrashEmpty => "The trash is empty.",
+            Text::Close => "Close",
+
+            Text::AxiomsUsed => "Axioms Used",
+            Text::AxiomsUsedBy => "Axioms used by {}",
+            Text::NoAxioms => "None at all!",
+            Text::ClassicalThrough => "Classical logic comes in through: {}.",
+            Text::Unrecorded => {
+                "These proofs were never recorded, and may rely on more axioms: {}."
+            }
+
+            Text::BackToBook => "Back to Book",
+            Text::BackToTheBook => "Back to the Book!",
+
+            Text::LevelEditor => "Level Editor",
+            Text::ToolMove => "Move",
+            Text::ToolConnect => "Connect",
+            Text::ToolHypothesis => "Hypothesis",
+            Text::ToolConclusion => "Conclusion",
+            Text::ToolDelete => "Delete",
+            Text::HelpMove => "Drag nodes to arrange them, or the background to pan.",
+            Text::HelpConnect => {
+                "Drag from a node onto another to make it the next input there. Drop it back on itself to remove its last input."
+            }
+            Text::HelpHypothesis => "Click a node to make it a hypothesis, or not.",
+            Text::HelpConclusion => "Click a node to make it the conclusion.",
+            Text::HelpDelete => "Click a node to delete it.",
+            Text::VariableName => "Variable name or numeral",
+            Text::AddTruthVariable => "Add Truth Value",
+            Text::AddNumberVariable => "Add Number",
+            Text::StatementValid => "The statement is valid.",
+            Text::NotTautology => {
+                "The statement is valid, but not a propositional tautology, since it fails when {}. \
+                 Proving it will need theorems, or reasoning about equality."
+            }
+            Text::Playtest => "Play-Test",
+            Text::ExportLevel => "Export Level",
+            Text::BackToEditor => "Back to Editor",
+            Text::BackToTheEditor => "Back to the Editor!",
+        }
+    }
+}
+
+pub struct Language {
+    pub code: String,
+    pub name: String,
+    pub strings: HashMap<Text, String>,
+}
+
+/// The game's text, in the chosen language.
+#[derive(Clone, Copy, Default)]
+pub struct Strings<'a>(Option<&'a Language>);
+
+impl<'a> Strings<'a> {
+    pub fn new(language: Option<&'a Language>) -> Self {
+        Self(language)
+    }
+
+    /// The code of the chosen language, or `None` for the default.
+    pub fn language(self) -> Option<&'a str> {
+        self.0.map(|language| language.code.as_str())
+    }
+
+    pub fn get(self, text: Text) -> &'a str {
+        self.0
+            .and_then(|language| language.strings.get(&text))
+            .map_or(text.english(), String::as_str)
+    }
+
+    /// Copy the text into the bump allocator, so it can be rendered.
+    pub fn get_in(self, bump: &dodrio::bumpalo::Bump, text: Text) -> &str {
+        dodrio::bumpalo::collections::String::from_str_in(self.get(text), bump).into_bump_str()
+    }
+
+    /// The text, with `{}` replaced by `arg`.
+    pub fn format(self, text: Text, arg: &str) -> String {
+        self.get(text).replacen("{}", arg, 1)
+    }
+}
+
+/// Text from a level pack, along with its translations.
+#[derive(Clone, Debug, Default)]
+pub struct Translated {
+    default: String,
+    translations: HashMap<String, String>,
+}
+
+impl Translated {
+    pub fn new(default: String, translations: HashMap<String, String>) -> Self {
+        Self {
+            default,
+            translations,
+        }
+    }
+
+    pub fn get(&self, strings: Strings) -> &str {
+        strings
+            .language()
+            .and_then(|language| self.translations.get(language))
+            .unwrap_or(&self.default)
+    }
+}
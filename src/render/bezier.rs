@@ -49,3 +49,184 @@ pub fn average(vs: &[[f64; 2]]) -> [f64; 2] {
     let n = vs.len() as f64;
     [x / n, y / n]
 }
+
+/// How wires are drawn. The player can choose, since routed wires are easier to follow
+/// in a crowded case, but curved ones keep their shape better while nodes are dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WireStyle {
+    /// Each wire is a few curves meeting at its midpoint.
+    #[default]
+    Curved,
+    /// Wires bend around nodes, and parallel wires are spread apart.
+    Routed,
+}
+
+/// Routes wires around the nodes of a case.
+pub struct Router {
+    /// The centers of the nodes, which have radius `NODE_RADIUS`.
+    obstacles: Vec<[f64; 2]>,
+    /// The midpoints of the wires routed so far.
+    midpoints: Vec<[f64; 2]>,
+}
+
+const NODE_RADIUS: f64 = 0.5;
+/// How far from a node's center a detour passes.
+const CLEARANCE: f64 = 0.8;
+/// How close to each other the midpoints of two wires may be.
+const SPACING: f64 = 0.4;
+const MAX_DETOURS: u32 = 4;
+const SAMPLES: u32 = 16;
+
+impl Router {
+    pub fn new(obstacles: Vec<[f64; 2]>) -> Self {
+        Self {
+            obstacles,
+            midpoints: Vec::new(),
+        }
+    }
+
+    /// Move a wire's midpoint off of any node and away from other wires' midpoints,
+    /// sideways to the direction the wire travels through it.
+    pub fn place_midpoint(&mut self, mut mid: [f64; 2], mid_vector: [f64; 2]) -> [f64; 2] {
+        let side = normalize([-mid_vector[1], mid_vector[0]]).unwrap_or([1., 0.]);
+        for _ in 0..8 {
+            let crowded = self
+                .midpoints
+                .iter()
+                .any(|&other| distance(mid, other) < SPACING)
+                || self
+                    .obstacles
+                    .iter()
+                    .any(|&node| distance(mid, node) < NODE_RADIUS);
+            if !crowded {
+                break;
+            }
+            mid = [mid[0] + side[0] * SPACING, mid[1] + side[1] * SPACING];
+        }
+        self.midpoints.push(mid);
+        mid
+    }
+
+    /// Like `path`, but detours around any node the curve would pass through,
+    /// other than those at its ends.
+    pub fn path(
+        &self,
+        start: [f64; 2],
+        start_vector: [f64; 2],
+        end_vector: [f64; 2],
+        end: [f64; 2],
+        d: &mut dodrio::bumpalo::collections::String,
+    ) {
+        self.route(start, start_vector, end_vector, end, 0, d)
+    }
+
+    fn route(
+        &self,
+        start: [f64; 2],
+        start_vector: [f64; 2],
+        end_vector: [f64; 2],
+        end: [f64; 2],
+        depth: u32,
+        d: &mut dodrio::bumpalo::collections::String,
+    ) {
+        let collision = (depth < MAX_DETOURS)
+            .then(|| self.first_collision(start, start_vector, end_vector, end))
+            .flatten();
+        let Some((node, closest)) = collision else {
+            return path(start, start_vector, end_vector, end, d);
+        };
+
+        // Go around the node on whichever side the curve was closest to.
+        let chord = [end[0] - start[0], end[1] - start[1]];
+        let away = normalize([closest[0] - node[0], closest[1] - node[1]])
+            .or_else(|| normalize([-chord[1], chord[0]]))
+            .unwrap_or([1., 0.]);
+        let waypoint = [node[0] + away[0] * CLEARANCE, node[1] + away[1] * CLEARANCE];
+
+        // The curve passes through the waypoint parallel to the chord, so the two halves join smoothly.
+        let length = (chord[0].hypot(chord[1]) / 4.).min(1.);
+        let tangent = normalize(chord).unwrap_or([0., 1.]);
+        let tangent = [tangent[0] * length, tangent[1] * length];
+
+        self.route(start, start_vector, tangent, waypoint, depth + 1, d);
+        self.route(waypoint, tangent, end_vector, end, depth + 1, d);
+    }
+
+    /// The first node the curve passes through, and the point of the curve nearest its center.
+    fn first_collision(
+        &self,
+        start: [f64; 2],
+        start_vector: [f64; 2],
+        end_vector: [f64; 2],
+        end: [f64; 2],
+    ) -> Option<([f64; 2], [f64; 2])> {
+        let controls = [
+            start,
+            [start[0] + start_vector[0], start[1] + start_vector[1]],
+            [end[0] - end_vector[0], end[1] - end_vector[1]],
+            end,
+        ];
+
+        // The curve lies within the bounding box of its control points.
+        let min = |i: usize| controls.iter().map(|p| p[i]).fold(f64::INFINITY, f64::min);
+        let max = |i: usize| {
+            controls
+                .iter()
+                .map(|p| p[i])
+                .fold(f64::NEG_INFINITY, f64::max)
+        };
+        let (x0, x1, y0, y1) = (min(0), max(0), min(1), max(1));
+        let nearby = self.obstacles.iter().filter(|&&[x, y]| {
+            x > x0 - NODE_RADIUS
+                && x < x1 + NODE_RADIUS
+                && y > y0 - NODE_RADIUS
+                && y < y1 + NODE_RADIUS
+                && distance([x, y], start) > NODE_RADIUS
+                && distance([x, y], end) > NODE_RADIUS
+        });
+
+        let samples: Vec<[f64; 2]> = (1..SAMPLES)
+            .map(|i| evaluate(controls, i as f64 / SAMPLES as f64))
+            .collect();
+
+        // The sample index where the curve enters the node, the node, and the nearest sample to it.
+        let mut first: Option<(usize, [f64; 2], [f64; 2])> = None;
+        for &node in nearby {
+            let Some(entry) = samples
+                .iter()
+                .position(|&point| distance(point, node) < NODE_RADIUS)
+            else {
+                continue;
+            };
+            if first.is_some_and(|(i, _, _)| i <= entry) {
+                continue;
+            }
+            let nearest = samples[entry..]
+                .iter()
+                .copied()
+                .min_by(|&a, &b| distance(a, node).total_cmp(&distance(b, node)))
+                .unwrap();
+            first = Some((entry, node, nearest));
+        }
+        first.map(|(_, node, point)| (node, point))
+    }
+}
+
+fn evaluate([p0, p1, p2, p3]: [[f64; 2]; 4], t: f64) -> [f64; 2] {
+    let s = 1. - t;
+    let [a, b, c, d] = [s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t];
+    [
+        a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+        a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+    ]
+}
+
+fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    (a[0] - b[0]).hypot(a[1] - b[1])
+}
+
+fn normalize([x, y]: [f64; 2]) -> Option<[f64; 2]> {
+    let r = x.hypot(y);
+    (r > 1e-9).then(|| [x / r, y / r])
+}
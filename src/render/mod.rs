@@ -25,16 +25,23 @@ pub fn text_(
     builder.namespace(Some("http://www.w3.org/2000/svg"))
 }
 
+/// The position of a mouse event, in the coordinates of the SVG it's handled in:
+/// either the element with the listener, or the SVG that element is part of.
+/// Looking the SVG up by id would find the wrong one when, say, a sandbox is open on top of a level.
 // https://stackoverflow.com/a/42711775
-pub fn to_svg_coords(e: web_sys::MouseEvent, id: &str) -> (f64, f64) {
-    let svg: web_sys::SvgsvgElement = web_sys::window()
+pub fn to_svg_coords(e: web_sys::MouseEvent) -> (f64, f64) {
+    let svg = match e
+        .current_target()
         .unwrap()
-        .document()
-        .unwrap()
-        .get_element_by_id(id)
-        .unwrap()
-        .dyn_into()
-        .unwrap();
+        .dyn_into::<web_sys::SvgsvgElement>()
+    {
+        Ok(svg) => svg,
+        Err(element) => element
+            .dyn_into::<web_sys::SvgElement>()
+            .unwrap()
+            .owner_svg_element()
+            .unwrap(),
+    };
 
     let pt: web_sys::SvgPoint = svg.create_svg_point();
     pt.set_x(e.client_x() as f32);
@@ -43,6 +50,48 @@ pub fn to_svg_coords(e: web_sys::MouseEvent, id: &str) -> (f64, f64) {
     (out.x() as f64, out.y() as f64)
 }
 
+/// Part of the view that is expensive to render, and only redrawn when marked stale.
+/// In between, dodrio reuses the previous rendering without even diffing it.
+pub struct Layer<R: Default> {
+    cached: std::cell::RefCell<dodrio::Cached<R>>,
+    stale: std::cell::Cell<bool>,
+}
+
+impl<R: Default> Layer<R> {
+    pub fn new() -> Self {
+        Self {
+            cached: Default::default(),
+            stale: std::cell::Cell::new(true),
+        }
+    }
+
+    /// Mark the layer stale, and let go of what it drew,
+    /// which may share data with the model, like a case's e-graph, that the model is about to change.
+    pub fn invalidate(&self) {
+        self.stale.set(true);
+        *self.cached.borrow_mut() = Default::default();
+    }
+
+    /// What was drawn most recently.
+    pub fn contents(&self) -> std::cell::Ref<'_, R> {
+        std::cell::Ref::map(self.cached.borrow(), |cached| &**cached)
+    }
+}
+
+impl<R: 'static + Default + for<'b> dodrio::Render<'b>> Layer<R> {
+    /// Render the layer, calling `contents` for what to draw if it's stale.
+    pub fn render<'a>(
+        &self,
+        cx: &mut dodrio::RenderContext<'a>,
+        contents: impl FnOnce() -> R,
+    ) -> dodrio::Node<'a> {
+        if self.stale.replace(false) {
+            *self.cached.borrow_mut() = dodrio::Cached::new(contents());
+        }
+        dodrio::Render::render(&*self.cached.borrow(), cx)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct PanZoom {
     pub svg_corners: ([f64; 2], [f64; 2]),
@@ -54,6 +103,26 @@ impl PanZoom {
             svg_corners: ([x - r, y - r], [x + r, y + r]),
         }
     }
+
+    /// A view showing every position, with room for the wires below.
+    pub fn fit(positions: impl IntoIterator<Item = [f64; 2]>) -> Self {
+        let mut x_min = f64::INFINITY;
+        let mut y_min = f64::INFINITY;
+        let mut x_max = f64::NEG_INFINITY;
+        let mut y_max = f64::NEG_INFINITY;
+
+        for [x, y] in positions {
+            x_min = x_min.min(x);
+            y_min = y_min.min(y);
+            x_max = x_max.max(x);
+            y_max = y_max.max(y);
+        }
+
+        Self {
+            svg_corners: ([x_min - 1., y_min - 1.], [x_max + 1., y_max + 3.]),
+        }
+    }
+
     pub fn pan(&mut self, dx: f64, dy: f64) {
         self.svg_corners.0[0] -= dx;
         self.svg_corners.1[0] -= dx;
@@ -0,0 +1,99 @@
+//! Routes that mirror the `GameState` in the location hash, such as `#/level/Intro.2`.
+//!
+//! Book pages also live in the hash, but never start with `/`, so the two don't collide.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    Map,
+    Editor,
+    Level(String),
+    SelectTheorem(String),
+}
+
+impl Route {
+    pub fn parse(hash: &str) -> Option<Self> {
+        let hash = String::from(js_sys::decode_uri_component(hash).ok()?);
+        Self::parse_decoded(&hash)
+    }
+
+    /// Parse a hash whose escapes have already been decoded.
+    fn parse_decoded(hash: &str) -> Option<Self> {
+        let path = hash.strip_prefix("#/")?;
+        if path == "map" {
+            return Some(Route::Map);
+        }
+        if path == "editor" {
+            return Some(Route::Editor);
+        }
+        let level = path.strip_prefix("level/")?;
+        Some(match level.strip_suffix("/select-theorem") {
+            Some(level) => Route::SelectTheorem(level.to_owned()),
+            None => Route::Level(level.to_owned()),
+        })
+    }
+
+    pub fn to_hash(&self) -> String {
+        self.to_hash_with(|name| String::from(js_sys::encode_uri_component(name)))
+    }
+
+    /// The hash, with level names escaped by `encode`.
+    fn to_hash_with(&self, encode: impl Fn(&str) -> String) -> String {
+        match self {
+            Route::Map => "#/map".to_owned(),
+            Route::Editor => "#/editor".to_owned(),
+            Route::Level(level) => format!("#/level/{}", encode(level)),
+            Route::SelectTheorem(level) => format!("#/level/{}/select-theorem", encode(level)),
+        }
+    }
+}
+
+/// Whether the hash refers to a route, rather than a book page or nothing at all.
+pub fn is_route(hash: &str) -> bool {
+    hash.starts_with("#/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_route() {
+        assert_eq!(Route::parse_decoded("#/map"), Some(Route::Map));
+        assert_eq!(Route::parse_decoded("#/editor"), Some(Route::Editor));
+        assert_eq!(
+            Route::parse_decoded("#/level/Intro.2"),
+            Some(Route::Level("Intro.2".to_owned()))
+        );
+        assert_eq!(
+            Route::parse_decoded("#/level/Intro.2/select-theorem"),
+            Some(Route::SelectTheorem("Intro.2".to_owned()))
+        );
+    }
+
+    #[test]
+    fn rejects_other_hashes() {
+        for hash in ["", "#", "#map", "#/", "#/levels", "#/level", "#intro"] {
+            assert_eq!(Route::parse_decoded(hash), None, "{hash}");
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        for route in [
+            Route::Map,
+            Route::Editor,
+            Route::Level("−-def".to_owned()),
+            Route::SelectTheorem("∧-intro".to_owned()),
+        ] {
+            let hash = route.to_hash_with(str::to_owned);
+            assert!(is_route(&hash));
+            assert_eq!(Route::parse_decoded(&hash), Some(route));
+        }
+    }
+
+    #[test]
+    fn book_pages_are_not_routes() {
+        assert!(!is_route("#intro"));
+        assert!(!is_route(""));
+    }
+}
@@ -0,0 +1,190 @@
+//! Choosing a theorem to apply, either on the world map or from a list that can be searched.
+
+use dodrio::{builder::*, bumpalo};
+use wasm_bindgen::JsCast;
+
+use crate::{
+    architecture::Architecture,
+    game_data::{GameData, SaveData},
+    level,
+    locale::{Strings, Text},
+    world_map, Model,
+};
+
+pub struct State {
+    pub map_state: world_map::State,
+    /// The theorem hovered over, whose statement is drawn.
+    pub preview: Option<usize>,
+    query: String,
+    /// Only list theorems whose conclusion matches the goal, so that they could be applied backward.
+    matching_goal: bool,
+    /// The theorems available when the list was opened, in order, with what's searched through.
+    /// They're found once, rather than on every keystroke.
+    theorems: Vec<Theorem>,
+}
+
+struct Theorem {
+    level: usize,
+    /// The level's name, in lowercase.
+    name: String,
+    statement: String,
+    /// Whether the conclusion matches the goal, found the first time the list is limited to those.
+    matches_goal: Option<bool>,
+}
+
+#[derive(Debug)]
+pub enum Msg {
+    Search(String),
+    ToggleMatchingGoal,
+}
+
+impl State {
+    pub fn new(game_data: &GameData, save_data: &SaveData) -> Self {
+        let theorems = (0..game_data.num_levels())
+            .filter(|&level| save_data.theorem_available(game_data, level))
+            .map(|level| {
+                let data = game_data.level(level);
+                Theorem {
+                    level,
+                    name: data.name().to_lowercase(),
+                    statement: data.spec.statement(),
+                    matches_goal: None,
+                }
+            })
+            .collect();
+        Self {
+            map_state: world_map::State::new(),
+            preview: None,
+            query: String::new(),
+            matching_goal: false,
+            theorems,
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        msg: Msg,
+        game_data: &GameData,
+        level_state: &level::State,
+        rerender: &mut bool,
+    ) {
+        match msg {
+            Msg::Search(query) => self.query = query,
+            Msg::ToggleMatchingGoal => {
+                self.matching_goal = !self.matching_goal;
+                for theorem in &mut self.theorems {
+                    theorem.matches_goal.get_or_insert_with(|| {
+                        level_state.theorem_matches_goal(&game_data.level(theorem.level).spec)
+                    });
+                }
+            }
+        }
+        *rerender = true;
+    }
+
+    /// Whether the list shows `theorem`.
+    /// Each word of the query must appear in the level's name or the theorem's statement.
+    fn shows(&self, theorem: &Theorem) -> bool {
+        let found = self.query.split_whitespace().all(|word| {
+            theorem.name.contains(&word.to_lowercase()) || theorem.statement.contains(word)
+        });
+        found && (!self.matching_goal || theorem.matches_goal == Some(true))
+    }
+
+    /// The search box, and the theorems it finds.
+    pub fn render_list<'a>(
+        &self,
+        cx: &mut dodrio::RenderContext<'a>,
+        strings: Strings,
+        game_data: &GameData,
+    ) -> [dodrio::Node<'a>; 3] {
+        let search = input(cx.bump)
+            .attributes([
+                attr("class", "text-box theorem-search"),
+                attr("type", "search"),
+                attr("placeholder", strings.get_in(cx.bump, Text::SearchTheorems)),
+                attr(
+                    "value",
+                    bumpalo::collections::String::from_str_in(&self.query, cx.bump).into_bump_str(),
+                ),
+            ])
+            .listeners([Model::listener(cx.bump, "input", |e| {
+                let query = e
+                    .target()
+                    .unwrap()
+                    .dyn_into::<web_sys::HtmlInputElement>()
+                    .unwrap()
+                    .value();
+                crate::Msg::TheoremSelect(Msg::Search(query))
+            })])
+            .finish();
+
+        let matching_goal = div(cx.bump)
+            .attributes([attr(
+                "class",
+                if self.matching_goal {
+                    "button green"
+                } else {
+                    "button"
+                },
+            )])
+            .listeners([Model::listener(cx.bump, "click", |_| {
+                crate::Msg::TheoremSelect(Msg::ToggleMatchingGoal)
+            })])
+            .children([text(strings.get_in(
+                cx.bump,
+                if self.matching_goal {
+                    Text::MatchingGoalOn
+                } else {
+                    Text::MatchingGoalOff
+                },
+            ))])
+            .finish();
+
+        let mut list = div(cx.bump).attributes([attr("class", "theorem-list background")]);
+        let mut empty = true;
+        for theorem in self.theorems.iter().filter(|theorem| self.shows(theorem)) {
+            empty = false;
+            let level = theorem.level;
+            let data = game_data.level(level);
+            list = list.child(
+                div(cx.bump)
+                    .attributes([attr(
+                        "class",
+                        if self.preview == Some(level) {
+                            "button blue"
+                        } else {
+                            "button"
+                        },
+                    )])
+                    .listeners([
+                        Model::listener(cx.bump, "click", move |_| {
+                            crate::Msg::SelectedTheorem(Some(level))
+                        }),
+                        Model::listener(cx.bump, "mouseover", move |_| {
+                            crate::Msg::PreviewTheorem(level)
+                        }),
+                    ])
+                    .children([
+                        b(cx.bump)
+                            .children([text(
+                                bumpalo::collections::String::from_str_in(data.name(), cx.bump)
+                                    .into_bump_str(),
+                            )])
+                            .finish(),
+                        text("\u{00a0}"),
+                        text(
+                            bumpalo::collections::String::from_str_in(&theorem.statement, cx.bump)
+                                .into_bump_str(),
+                        ),
+                    ])
+                    .finish(),
+            );
+        }
+        if empty {
+            list = list.child(text(strings.get_in(cx.bump, Text::NoTheoremsFound)));
+        }
+
+        [search, matching_goal, list.finish()]
+    }
+}
@@ -4,6 +4,8 @@ mod render;
 
 pub struct State {
     drag: Option<[f64; 2]>,
+    /// In audit mode, clicking a completed level shows the axioms it relies on, instead of entering it.
+    audit: bool,
 }
 
 #[derive(Debug)]
@@ -13,11 +15,15 @@ pub enum Msg {
     MouseMove(f64, f64),
     MouseUp(f64, f64),
     MouseWheel(f64, f64, f64),
+    ToggleAudit,
 }
 
 impl State {
     pub fn new() -> Self {
-        Self { drag: None }
+        Self {
+            drag: None,
+            audit: false,
+        }
     }
 
     pub fn update(&mut self, msg: Msg, panzoom: &mut PanZoom, rerender: &mut bool) {
@@ -37,6 +43,10 @@ impl State {
                 panzoom.zoom(x, y, (wheel * 0.001).exp());
                 *rerender = true
             }
+            Msg::ToggleAudit => {
+                self.audit = !self.audit;
+                *rerender = true
+            }
         }
     }
 
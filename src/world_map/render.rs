@@ -1,5 +1,11 @@
 use super::*;
-use crate::{architecture::Architecture, game_data::GameData, render::*, Model};
+use crate::{
+    architecture::Architecture,
+    game_data::GameData,
+    locale::{Strings, Text},
+    render::{bezier::WireStyle, *},
+    Model,
+};
 use dodrio::{builder::*, bumpalo};
 use wasm_bindgen::JsCast;
 
@@ -29,21 +35,21 @@ impl State {
             ])
             .listeners([
                 Model::listener(cx.bump, "mousedown", move |e| {
-                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "map");
+                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
                     crate::Msg::WorldMap(Msg::MouseDown(x, y))
                 }),
                 Model::listener(cx.bump, "mouseup", move |e| {
-                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "map");
+                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
                     crate::Msg::WorldMap(Msg::MouseUp(x, y))
                 }),
                 Model::listener(cx.bump, "mousemove", move |e| {
-                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap(), "map");
+                    let (x, y) = to_svg_coords(e.dyn_into::<web_sys::MouseEvent>().unwrap());
                     crate::Msg::WorldMap(Msg::MouseMove(x, y))
                 }),
                 Model::listener(cx.bump, "wheel", move |e| {
                     let e = e.dyn_into::<web_sys::WheelEvent>().unwrap();
                     let wheel = e.delta_y();
-                    let (x, y) = to_svg_coords(e.into(), "map");
+                    let (x, y) = to_svg_coords(e.into());
                     crate::Msg::WorldMap(Msg::MouseWheel(x, y, wheel))
                 }),
             ]);
@@ -98,10 +104,12 @@ impl State {
                     bumpalo::format!(in cx.bump, "node{}{}", if game_data.level(level).axiom {" axiom"} else {""}, if save_data.completed(level) {
                         if Some(Some(level)) == is_theorem_select {
                             " hoverable known goal"
+                        } else if is_theorem_select.is_some() && !save_data.theorem_available(game_data, level) {
+                            " known"
                         } else {
                             " hoverable known"
                         }
-                    } else if is_theorem_select.is_none() && prereqs_complete {
+                    } else if is_theorem_select.is_none() && !self.audit && prereqs_complete {
                         " hoverable goal"
                     } else {
                         ""
@@ -112,7 +120,7 @@ impl State {
 
             #[allow(clippy::collapsible_else_if)]
             if is_theorem_select.is_some() {
-                if save_data.completed(level) {
+                if save_data.theorem_available(game_data, level) {
                     circle = circle.listeners(bumpalo::vec![in cx.bump;
                     Model::listener(cx.bump,
                         "click",
@@ -122,6 +130,10 @@ impl State {
                         move |_| crate::Msg::PreviewTheorem(level),
                     )])
                 }
+            } else if self.audit {
+                if save_data.completed(level) {
+                    circle = circle.listeners(bumpalo::vec![in cx.bump; Model::listener(cx.bump, "click", move |_| crate::Msg::ShowAudit(Some(level)))]);
+                }
             } else {
                 if prereqs_complete {
                     circle = circle.listeners(bumpalo::vec![in cx.bump; Model::listener(cx.bump, "click", move |_| crate::Msg::GotoLevel(level))]);
@@ -129,8 +141,155 @@ impl State {
             }
 
             builder = builder.child(circle.finish());
+
+            if save_data.constructive()
+                && save_data.completed(level)
+                && save_data.audit(game_data, level).constructive()
+            {
+                let [x, y] = game_data.level(level).map_position;
+                builder = builder.child(
+                    text_(cx.bump)
+                        .attributes([
+                            attr("class", "constructive-mark"),
+                            attr("x", bumpalo::format!(in cx.bump, "{}", x).into_bump_str()),
+                            attr(
+                                "y",
+                                bumpalo::format!(in cx.bump, "{}", y - 0.8).into_bump_str(),
+                            ),
+                        ])
+                        .children([text("✓")])
+                        .finish(),
+                );
+            }
+
+            if let Some(stars) = save_data
+                .best(level)
+                .and_then(|best| game_data.level(level).stars(best))
+            {
+                let [x, y] = game_data.level(level).map_position;
+                builder = builder.child(
+                    text_(cx.bump)
+                        .attributes([
+                            attr("class", "stars"),
+                            attr("x", bumpalo::format!(in cx.bump, "{}", x).into_bump_str()),
+                            attr(
+                                "y",
+                                bumpalo::format!(in cx.bump, "{}", y + 0.8).into_bump_str(),
+                            ),
+                        ])
+                        .children([text(["☆☆☆", "★☆☆", "★★☆", "★★★"][stars as usize])])
+                        .finish(),
+                );
+            }
         }
 
         builder.finish()
     }
+
+    pub fn constructive_button<'a>(
+        cx: &mut dodrio::RenderContext<'a>,
+        strings: Strings,
+        save_data: &crate::SaveData,
+    ) -> dodrio::Node<'a> {
+        div(cx.bump)
+            .attributes([attr("class", "button cyan")])
+            .listeners([Model::listener(cx.bump, "click", |_| {
+                crate::Msg::ToggleConstructive
+            })])
+            .children([text(strings.get_in(
+                cx.bump,
+                if save_data.constructive() {
+                    Text::ConstructiveModeOn
+                } else {
+                    Text::ConstructiveModeOff
+                },
+            ))])
+            .finish()
+    }
+
+    pub fn wire_style_button<'a>(
+        cx: &mut dodrio::RenderContext<'a>,
+        strings: Strings,
+        save_data: &crate::SaveData,
+    ) -> dodrio::Node<'a> {
+        div(cx.bump)
+            .attributes([attr("class", "button cyan")])
+            .listeners([Model::listener(cx.bump, "click", |_| {
+                crate::Msg::ToggleWireStyle
+            })])
+            .children([text(strings.get_in(
+                cx.bump,
+                match save_data.wire_style() {
+                    WireStyle::Curved => Text::WireStyleCurved,
+                    WireStyle::Routed => Text::WireStyleRouted,
+                },
+            ))])
+            .finish()
+    }
+
+    /// A button cycling through the pack's languages, or `None` if it has no translations.
+    pub fn language_button<'a>(
+        cx: &mut dodrio::RenderContext<'a>,
+        game_data: &GameData,
+        save_data: &crate::SaveData,
+    ) -> Option<dodrio::Node<'a>> {
+        if game_data.languages().is_empty() {
+            return None;
+        }
+        let strings = game_data.strings(save_data.language());
+        let name = game_data
+            .languages()
+            .iter()
+            .find(|l| Some(l.code.as_str()) == save_data.language())
+            .map_or("English", |l| l.name.as_str());
+        Some(
+            div(cx.bump)
+                .attributes([attr("class", "button cyan")])
+                .listeners([Model::listener(cx.bump, "click", |_| {
+                    crate::Msg::NextLanguage
+                })])
+                .children([text(
+                    bumpalo::collections::String::from_str_in(
+                        &strings.format(Text::Language, name),
+                        cx.bump,
+                    )
+                    .into_bump_str(),
+                )])
+                .finish(),
+        )
+    }
+
+    pub fn editor_button<'a>(
+        cx: &mut dodrio::RenderContext<'a>,
+        strings: Strings,
+    ) -> dodrio::Node<'a> {
+        div(cx.bump)
+            .attributes([attr("class", "button yellow")])
+            .listeners([Model::listener(cx.bump, "click", |_| {
+                crate::Msg::OpenEditor
+            })])
+            .children([text(strings.get_in(cx.bump, Text::LevelEditor))])
+            .finish()
+    }
+
+    pub fn audit_button<'a>(
+        &self,
+        cx: &mut dodrio::RenderContext<'a>,
+        strings: Strings,
+    ) -> dodrio::Node<'a> {
+        div(cx.bump)
+            .attributes([attr("class", "button cyan")])
+            .listeners([Model::listener(cx.bump, "click", |_| {
+                crate::Msg::WorldMap(Msg::ToggleAudit)
+            })])
+            .children([text(strings.get_in(
+                cx.bump,
+                if self.audit {
+                    Text::StopAuditing
+                } else {
+                    Text::AuditAxioms
+                },
+            ))])
+            .finish()
+    }
 }